@@ -0,0 +1,311 @@
+use crate::{cube::check_axis, matrix::Matrix, object::Shape, ray::Ray, tuple::Tuple};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        BoundingBox { min, max }
+    }
+    pub fn empty() -> Self {
+        BoundingBox {
+            min: Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        let merge = |min: &mut f64, max: &mut f64, v: f64| {
+            if v.is_nan() {
+                *min = f64::NEG_INFINITY;
+                *max = f64::INFINITY;
+            } else {
+                *min = min.min(v);
+                *max = max.max(v);
+            }
+        };
+        merge(&mut self.min.x, &mut self.max.x, p.x);
+        merge(&mut self.min.y, &mut self.max.y, p.y);
+        merge(&mut self.min.z, &mut self.max.z, p.z);
+    }
+
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, p: Tuple) -> bool {
+        self.min.x <= p.x
+            && p.x <= self.max.x
+            && self.min.y <= p.y
+            && p.y <= self.max.y
+            && self.min.z <= p.z
+            && p.z <= self.max.z
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn transform(&self, m: &Matrix) -> BoundingBox {
+        let mut result = BoundingBox::empty();
+        if self.is_empty() {
+            return result;
+        }
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    result.add_point(transform_point(m, Tuple::point(x, y, z)));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax && tmax >= 0.
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let d = self.max - self.min;
+        let area = 2. * (d.x * d.y + d.y * d.z + d.z * d.x);
+        if area.is_nan() {
+            f64::INFINITY
+        } else {
+            area
+        }
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        let mid = |min: f64, max: f64| {
+            if min.is_infinite() || max.is_infinite() {
+                0.
+            } else {
+                (min + max) / 2.
+            }
+        };
+        Tuple::point(
+            mid(self.min.x, self.max.x),
+            mid(self.min.y, self.max.y),
+            mid(self.min.z, self.max.z),
+        )
+    }
+}
+
+fn transform_point(m: &Matrix, p: Tuple) -> Tuple {
+    let coords = [p.x, p.y, p.z, p.w];
+    let row = |r: usize| -> f64 {
+        (0..4)
+            .filter(|&c| m[(r, c)] != 0.)
+            .map(|c| m[(r, c)] * coords[c])
+            .sum()
+    };
+    Tuple::point(row(0), row(1), row(2))
+}
+
+pub fn parent_space_bounds(s: &dyn Shape) -> BoundingBox {
+    s.bounds().transform(s.transform())
+}
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+
+    use crate::{
+        cone::Cone,
+        cube::Cube,
+        cylinder::Cylinder,
+        group::Group,
+        matrix::Matrix,
+        object::Shape,
+        plane::Plane,
+        ray::Ray,
+        sphere::Sphere,
+        triangle::Triangle,
+        tuple::{assert_tuple_eq, Tuple},
+    };
+
+    use super::{parent_space_bounds, BoundingBox};
+
+    #[test]
+    fn test_empty_box() {
+        let b = BoundingBox::empty();
+        assert!(b.is_empty());
+        assert_eq!(
+            b.min,
+            Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY)
+        );
+        assert_eq!(
+            b.max,
+            Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY)
+        );
+    }
+
+    #[test]
+    fn test_add_points() {
+        let mut b = BoundingBox::empty();
+        b.add_point(Tuple::point(-5., 2., 0.));
+        b.add_point(Tuple::point(7., 0., -3.));
+        assert_eq!(b.min, Tuple::point(-5., 0., -3.));
+        assert_eq!(b.max, Tuple::point(7., 2., 0.));
+    }
+
+    #[test]
+    fn test_shape_bounds() {
+        assert_eq!(
+            Sphere::new().bounds(),
+            BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+        );
+        assert_eq!(
+            Cube::new().bounds(),
+            BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+        );
+        let p = Plane::new().bounds();
+        assert_eq!(
+            p.min,
+            Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY)
+        );
+        assert_eq!(p.max, Tuple::point(f64::INFINITY, 0., f64::INFINITY));
+        assert_eq!(
+            Cylinder::truncated(-5., 3., false).bounds(),
+            BoundingBox::new(Tuple::point(-1., -5., -1.), Tuple::point(1., 3., 1.))
+        );
+        assert_eq!(
+            Cone::truncated(-5., 3., false).bounds(),
+            BoundingBox::new(Tuple::point(-5., -5., -5.), Tuple::point(5., 3., 5.))
+        );
+        let t = Triangle::new(
+            Tuple::point(-3., 7., 2.),
+            Tuple::point(6., 2., -4.),
+            Tuple::point(2., -1., -1.),
+        );
+        assert_eq!(
+            t.bounds(),
+            BoundingBox::new(Tuple::point(-3., -1., -4.), Tuple::point(6., 7., 2.))
+        );
+    }
+
+    #[test]
+    fn test_add_box() {
+        let mut b1 = BoundingBox::new(Tuple::point(-5., -2., 0.), Tuple::point(7., 4., 4.));
+        let b2 = BoundingBox::new(Tuple::point(8., -7., -2.), Tuple::point(14., 2., 8.));
+        b1.add_box(&b2);
+        assert_eq!(b1.min, Tuple::point(-5., -7., -2.));
+        assert_eq!(b1.max, Tuple::point(14., 4., 8.));
+    }
+
+    #[test]
+    fn test_contains() {
+        let b = BoundingBox::new(Tuple::point(5., -2., 0.), Tuple::point(11., 4., 7.));
+        assert!(b.contains_point(Tuple::point(5., -2., 0.)));
+        assert!(b.contains_point(Tuple::point(8., 1., 3.)));
+        assert!(!b.contains_point(Tuple::point(3., 0., 3.)));
+        assert!(!b.contains_point(Tuple::point(8., 1., 8.)));
+        assert!(b.contains_box(&BoundingBox::new(
+            Tuple::point(6., -1., 1.),
+            Tuple::point(10., 3., 6.)
+        )));
+        assert!(!b.contains_box(&BoundingBox::new(
+            Tuple::point(4., -3., -1.),
+            Tuple::point(10., 3., 6.)
+        )));
+    }
+
+    #[test]
+    fn test_transform_box() {
+        let b = BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.));
+        let m = Matrix::rot_x(PI / 4.) * Matrix::rot_y(PI / 4.);
+        let b2 = b.transform(&m);
+        let d = 1. + FRAC_1_SQRT_2;
+        assert_tuple_eq(b2.min, Tuple::point(-SQRT_2, -d, -d));
+        assert_tuple_eq(b2.max, Tuple::point(SQRT_2, d, d));
+    }
+
+    #[test]
+    fn test_transform_infinite_box() {
+        let mut p = Plane::new();
+        p.set_transform(Matrix::translation(1., 2., 3.))
+            .expect("Transform should be invertible");
+        let b = parent_space_bounds(&p);
+        assert_eq!(
+            b.min,
+            Tuple::point(f64::NEG_INFINITY, 2., f64::NEG_INFINITY)
+        );
+        assert_eq!(b.max, Tuple::point(f64::INFINITY, 2., f64::INFINITY));
+    }
+
+    #[test]
+    fn test_parent_space_bounds() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(1., -3., 5.) * Matrix::scale(0.5, 2., 4.))
+            .expect("Transform should be invertible");
+        let b = parent_space_bounds(&s);
+        assert_eq!(b.min, Tuple::point(0.5, -5., 1.));
+        assert_eq!(b.max, Tuple::point(1.5, -1., 9.));
+    }
+
+    #[test]
+    fn test_group_bounds() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(2., 5., -3.) * Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        let mut c = Cylinder::truncated(-2., 2., false);
+        c.set_transform(Matrix::translation(-4., -1., 4.) * Matrix::scale(0.5, 1., 0.5))
+            .expect("Transform should be invertible");
+        let mut g = Group::new();
+        g.add_child(Box::new(s));
+        g.add_child(Box::new(c));
+        let b = g.bounds();
+        assert_eq!(b.min, Tuple::point(-4.5, -3., -5.));
+        assert_eq!(b.max, Tuple::point(4., 7., 4.5));
+    }
+
+    #[test]
+    fn test_ray_intersects_box() {
+        let b = BoundingBox::new(Tuple::point(5., -2., 0.), Tuple::point(11., 4., 7.));
+        let cases = [
+            (Tuple::point(15., 1., 2.), Tuple::vector(-1., 0., 0.), true),
+            (Tuple::point(-5., -1., 4.), Tuple::vector(1., 0., 0.), true),
+            (Tuple::point(7., 6., 5.), Tuple::vector(0., -1., 0.), true),
+            (Tuple::point(9., -5., 6.), Tuple::vector(0., 1., 0.), true),
+            (Tuple::point(8., 2., 12.), Tuple::vector(0., 0., -1.), true),
+            (Tuple::point(6., 0., -5.), Tuple::vector(0., 0., 1.), true),
+            (Tuple::point(8., 1., 3.5), Tuple::vector(0., 0., 1.), true),
+            (Tuple::point(9., -1., -8.), Tuple::vector(2., 4., 6.), false),
+            (Tuple::point(8., 3., -4.), Tuple::vector(6., 2., 4.), false),
+            (Tuple::point(9., -1., -2.), Tuple::vector(4., 6., 2.), false),
+            (Tuple::point(4., 0., 9.), Tuple::vector(0., 0., -1.), false),
+            (Tuple::point(8., 6., -1.), Tuple::vector(0., -1., 0.), false),
+            (Tuple::point(12., 5., 4.), Tuple::vector(-1., 0., 0.), false),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected);
+        }
+    }
+
+    #[test]
+    fn test_surface_area() {
+        let b = BoundingBox::new(Tuple::point(0., 0., 0.), Tuple::point(1., 2., 3.));
+        assert_eq!(b.surface_area(), 22.);
+        assert_eq!(BoundingBox::empty().surface_area(), 0.);
+    }
+}
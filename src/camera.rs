@@ -0,0 +1,372 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::matrix::{Matrix, SingularMatrixError, Transform};
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+use crate::world::World;
+
+// The plastic number, whose R2 sequence spreads any number of samples evenly
+// over a pixel.
+const PLASTIC: f64 = 1.324_717_957_244_746;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub samples: usize,
+    pub threads: usize,
+    pub tile_size: usize,
+}
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            samples: 1,
+            threads: 1,
+            tile_size: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+pub fn tiles(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+    let size = tile_size.max(1);
+    let mut tiles = vec![];
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            });
+        }
+    }
+    tiles
+}
+
+pub fn sample_offset(i: usize) -> (f64, f64) {
+    let a1 = 1. / PLASTIC;
+    let a2 = 1. / (PLASTIC * PLASTIC);
+    ((0.5 + a1 * i as f64).fract(), (0.5 + a2 * i as f64).fract())
+}
+
+pub struct Camera {
+    pub hsize: usize,
+    pub vsize: usize,
+    pub field_of_view: f64,
+    transform: Transform,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+}
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+        let half_view = f64::tan(field_of_view / 2.);
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1. {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Transform::default(),
+            half_width,
+            half_height,
+            pixel_size: (half_width * 2.) / hsize as f64,
+        }
+    }
+    pub fn transform(&self) -> &Matrix {
+        &self.transform.matrix
+    }
+    pub fn set_transform(&mut self, m: Matrix) -> Result<(), SingularMatrixError> {
+        self.transform = Transform::new(m)?;
+        Ok(())
+    }
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_through(px as f64 + 0.5, py as f64 + 0.5)
+    }
+
+    pub fn ray_through(&self, x: f64, y: f64) -> Ray {
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let pixel = self.transform.inverse * Tuple::point(world_x, world_y, -1.);
+        let origin = self.transform.inverse * Tuple::point(0., 0., 0.);
+        Ray::new(origin, (pixel - origin).normalize())
+    }
+
+    pub fn pixel_color(&self, world: &World, px: usize, py: usize, samples: usize) -> Color {
+        let samples = samples.max(1);
+        // Starting from the first sample keeps a single sample bit-identical
+        // to tracing the pixel center directly.
+        let mut sum = world.color_at(&self.ray_for_pixel(px, py));
+        for i in 1..samples {
+            let (dx, dy) = sample_offset(i);
+            let r = self.ray_through(px as f64 + dx, py as f64 + dy);
+            sum = sum + world.color_at(&r);
+        }
+        sum / samples as f64
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let r = self.ray_for_pixel(x, y);
+                image.set_pix((x, y), world.color_at(&r));
+            }
+        }
+        image
+    }
+
+    // Workers pull tiles from a shared queue, render each one into a local
+    // buffer and take the canvas lock once per tile to copy it in.
+    pub fn render_with<F: Fn(usize, usize) + Sync>(
+        &self,
+        world: &World,
+        options: RenderOptions,
+        progress: F,
+    ) -> Canvas {
+        let queue = tiles(self.hsize, self.vsize, options.tile_size);
+        let next_tile = AtomicUsize::new(0);
+        let tiles_done = AtomicUsize::new(0);
+        let image = Mutex::new(Canvas::new(self.hsize, self.vsize));
+        let worker = || {
+            let mut buffer = vec![];
+            while let Some(tile) = queue.get(next_tile.fetch_add(1, Ordering::Relaxed)) {
+                buffer.clear();
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        buffer.push(self.pixel_color(world, x, y, options.samples));
+                    }
+                }
+                {
+                    let mut image = image.lock().expect("Canvas lock should not be poisoned");
+                    for (i, &color) in buffer.iter().enumerate() {
+                        image.set_pix((tile.x + i % tile.width, tile.y + i / tile.width), color);
+                    }
+                }
+                progress(tiles_done.fetch_add(1, Ordering::Relaxed) + 1, queue.len());
+            }
+        };
+        let threads = options.threads.clamp(1, queue.len().max(1));
+        thread::scope(|scope| {
+            for _ in 1..threads {
+                scope.spawn(worker);
+            }
+            worker();
+        });
+        image
+            .into_inner()
+            .expect("Canvas lock should not be poisoned")
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        color::Color,
+        matrix::Matrix,
+        tuple::{assert_tuple_eq, Tuple},
+        world::World,
+    };
+
+    use super::{sample_offset, tiles, Camera, RenderOptions, Tile};
+
+    #[test]
+    fn test_camera_new() {
+        let c = Camera::new(160, 120, PI / 2.);
+        assert_eq!(c.hsize, 160);
+        assert_eq!(c.vsize, 120);
+        assert_eq!(c.field_of_view, PI / 2.);
+        assert_eq!(*c.transform(), Matrix::identity());
+    }
+
+    #[test]
+    fn test_pixel_size_horizontal() {
+        let c = Camera::new(200, 125, PI / 2.);
+        assert!((c.pixel_size() - 0.01).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_pixel_size_vertical() {
+        let c = Camera::new(125, 200, PI / 2.);
+        assert!((c.pixel_size() - 0.01).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_ray_for_pixel_center() {
+        let c = Camera::new(201, 101, PI / 2.);
+        let r = c.ray_for_pixel(100, 50);
+        assert_tuple_eq(r.origin, Tuple::point(0., 0., 0.));
+        assert_tuple_eq(r.direction, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_ray_for_pixel_corner() {
+        let c = Camera::new(201, 101, PI / 2.);
+        let r = c.ray_for_pixel(0, 0);
+        assert_tuple_eq(r.origin, Tuple::point(0., 0., 0.));
+        assert_tuple_eq(r.direction, Tuple::vector(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn test_singular_camera_transform() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        let err = c
+            .set_transform(Matrix::scale(1., 0., 1.))
+            .expect_err("Should reject a singular transform");
+        assert_eq!(err.to_string(), "transform is not invertible");
+        assert_eq!(*c.transform(), Matrix::identity());
+    }
+
+    #[test]
+    fn test_ray_for_pixel_transformed() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.set_transform(Matrix::rot_y(PI / 4.) * Matrix::translation(0., -2., 5.))
+            .expect("Transform should be invertible");
+        let r = c.ray_for_pixel(100, 50);
+        assert_tuple_eq(r.origin, Tuple::point(0., 2., -5.));
+        assert_tuple_eq(
+            r.direction,
+            Tuple::vector(f64::sqrt(2.) / 2., 0., -f64::sqrt(2.) / 2.),
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let w = World::default_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        let from = Tuple::point(0., 0., -5.);
+        let to = Tuple::point(0., 0., 0.);
+        let up = Tuple::vector(0., 1., 0.);
+        c.set_transform(Matrix::view_transform(from, to, up))
+            .expect("Transform should be invertible");
+        let image = c.render(&w);
+        let pix = image.get_pix((5, 5));
+        let expected = Color::new(0.38066, 0.47583, 0.2855);
+        assert!((pix.r - expected.r).abs() < 0.0001);
+        assert!((pix.g - expected.g).abs() < 0.0001);
+        assert!((pix.b - expected.b).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_sample_offsets_stay_inside_the_pixel() {
+        assert_eq!(sample_offset(0), (0.5, 0.5));
+        for i in 1..64 {
+            let (dx, dy) = sample_offset(i);
+            assert!((0. ..1.).contains(&dx));
+            assert!((0. ..1.).contains(&dy));
+            assert_ne!((dx, dy), sample_offset(i - 1));
+        }
+    }
+
+    #[test]
+    fn test_tiles_cover_the_image() {
+        let t = tiles(5, 3, 2);
+        assert_eq!(t.len(), 6);
+        assert_eq!(
+            t[0],
+            Tile {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 2
+            }
+        );
+        assert_eq!(
+            t[5],
+            Tile {
+                x: 4,
+                y: 2,
+                width: 1,
+                height: 1
+            }
+        );
+        let area: usize = t.iter().map(|t| t.width * t.height).sum();
+        assert_eq!(area, 15);
+        assert!(tiles(0, 10, 4).is_empty());
+    }
+
+    #[test]
+    fn test_parallel_render_is_bit_identical() {
+        let mut w = World::default_world();
+        let mut mat = w.objects[1].material().clone();
+        mat.reflective = 0.5;
+        mat.transparency = 0.5;
+        mat.refractive_index = 1.5;
+        w.objects[1].set_material(mat);
+        let mut c = Camera::new(23, 17, PI / 2.);
+        c.set_transform(Matrix::view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ))
+        .expect("Transform should be invertible");
+        let single = c.render(&w);
+        for (threads, tile_size) in [(1, 16), (4, 5), (7, 1), (64, 100)] {
+            let options = RenderOptions {
+                samples: 1,
+                threads,
+                tile_size,
+            };
+            let image = c.render_with(&w, options, |_, _| {});
+            for y in 0..17 {
+                for x in 0..23 {
+                    let (a, b) = (image.get_pix((x, y)), single.get_pix((x, y)));
+                    assert_eq!(
+                        [a.r.to_bits(), a.g.to_bits(), a.b.to_bits()],
+                        [b.r.to_bits(), b.g.to_bits(), b.b.to_bits()]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_with_samples_and_progress() {
+        let w = World::default_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Matrix::view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ))
+        .expect("Transform should be invertible");
+        let single = c.render(&w);
+        let options = RenderOptions {
+            samples: 4,
+            threads: 3,
+            tile_size: 4,
+        };
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let image = c.render_with(&w, options, |done, total| {
+            assert!(done <= total);
+            assert_eq!(total, 9);
+            calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+        assert_eq!(calls.into_inner(), 9);
+        let expected = c.pixel_color(&w, 5, 5, 4);
+        assert_eq!(image.get_pix((5, 5)), expected);
+        assert_ne!(image.get_pix((5, 5)), single.get_pix((5, 5)));
+    }
+}
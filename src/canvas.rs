@@ -1,9 +1,14 @@
 use super::color;
-use std::io::Write;
+use crate::hdr::{to_rgbe, write_hdr};
+use crate::output_transform::OutputTransform;
+use crate::png::{write_png, BitDepth, Compression};
+use std::io::{self, Write};
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     width: usize,
     height: usize,
     c: Vec<Vec<color::Color>>,
+    output: OutputTransform,
 }
 
 impl Canvas {
@@ -12,6 +17,7 @@ impl Canvas {
             width,
             height,
             c: vec![vec![color::Color::new(0., 0., 0.); width]; height],
+            output: OutputTransform::default(),
         }
     }
     pub fn shape(&self) -> (usize, usize) {
@@ -23,46 +29,126 @@ impl Canvas {
     pub fn get_pix(&self, coord: (usize, usize)) -> color::Color {
         self.c[coord.1][coord.0]
     }
-    pub fn to_ppm<T: Write>(&self, writeable: &mut T) {
-        let _ = writeable.write("P3\n".as_bytes());
-        let _ = writeable.write(format!("{} {}\n", self.width, self.height).as_bytes());
-        let _ = writeable.write("255\n".as_bytes());
+    pub fn output_transform(&self) -> OutputTransform {
+        self.output
+    }
+    pub fn set_output_transform(&mut self, output: OutputTransform) {
+        self.output = output;
+    }
+    pub fn to_ppm<T: Write>(&self, writeable: &mut T) -> io::Result<()> {
+        write!(writeable, "P3\n{} {}\n255\n", self.width, self.height)?;
+        let mut line = String::new();
+        for row in self.c.iter() {
+            line.clear();
+            let mut line_len = 0;
+            for pix in row.iter().map(|&pix| self.output.apply(pix)) {
+                for channel in [pix.r, pix.g, pix.b] {
+                    let value = to_byte(channel).to_string();
+                    if line_len > 0 && line_len + 1 + value.len() > 70 {
+                        line.push('\n');
+                        line_len = 0;
+                    } else if line_len > 0 {
+                        line.push(' ');
+                        line_len += 1;
+                    }
+                    line_len += value.len();
+                    line.push_str(&value);
+                }
+            }
+            line.push('\n');
+            writeable.write_all(line.as_bytes())?;
+        }
+        writeable.flush()
+    }
+
+    pub fn to_ppm_binary<T: Write>(&self, writeable: &mut T) -> io::Result<()> {
+        write!(writeable, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let mut bytes = Vec::with_capacity(self.width * 3);
         for row in self.c.iter() {
-            let row_str = row
-                .iter()
-                .flat_map(|pix| {
-                    let r: u8 = f64::min(255.0, f64::round(pix.r * 255.0)) as u8;
-                    let g: u8 = f64::min(255.0, f64::round(pix.g * 255.0)) as u8;
-                    let b: u8 = f64::min(255.0, f64::round(pix.b * 255.0)) as u8;
-
-                    let r = format!("{r}");
-                    let g = format!("{g}");
-                    let b = format!("{b}");
-                    vec![r, g, b]
-                })
-                .reduce(|mut acc, i| {
-                    let l = acc.len();
-                    if l % 70 <= (l + 1 + i.len()) % 70 {
-                        acc.push(' ');
-                        acc = acc + &i;
-                    } else {
-                        acc.push('\n');
-                        acc = acc + &i;
+            bytes.clear();
+            for pix in row.iter().map(|&pix| self.output.apply(pix)) {
+                bytes.extend([to_byte(pix.r), to_byte(pix.g), to_byte(pix.b)]);
+            }
+            writeable.write_all(&bytes)?;
+        }
+        writeable.flush()
+    }
+
+    pub fn to_png<T: Write>(
+        &self,
+        writeable: &mut T,
+        depth: BitDepth,
+        compression: Compression,
+    ) -> io::Result<()> {
+        let sample_size = match depth {
+            BitDepth::Eight => 1,
+            BitDepth::Sixteen => 2,
+        };
+        let mut scanlines = Vec::with_capacity(self.height * (1 + self.width * 3 * sample_size));
+        for row in self.c.iter() {
+            scanlines.push(0);
+            for pix in row.iter().map(|&pix| self.output.apply(pix)) {
+                for channel in [pix.r, pix.g, pix.b] {
+                    match depth {
+                        BitDepth::Eight => scanlines.push(to_byte(channel)),
+                        BitDepth::Sixteen => scanlines.extend(to_word(channel).to_be_bytes()),
                     }
-                    acc
-                });
-            if row_str.is_some() {
-                let s = row_str.expect("Should have lines");
-                let _ = writeable.write(s.as_bytes());
+                }
             }
+        }
+        write_png(
+            writeable,
+            self.width,
+            self.height,
+            depth,
+            compression,
+            &scanlines,
+        )
+    }
 
-            let _ = writeable.write("\n".as_bytes());
+    pub fn to_pfm<T: Write>(&self, writeable: &mut T) -> io::Result<()> {
+        // A negative scale marks the samples as little-endian.
+        write!(writeable, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        let mut bytes = Vec::with_capacity(self.width * 3 * 4);
+        for row in self.c.iter().rev() {
+            bytes.clear();
+            for pix in row.iter().map(|&pix| self.output.apply(pix)) {
+                for channel in [pix.r, pix.g, pix.b] {
+                    bytes.extend((channel as f32).to_le_bytes());
+                }
+            }
+            writeable.write_all(&bytes)?;
         }
+        writeable.flush()
     }
+
+    pub fn to_hdr<T: Write>(&self, writeable: &mut T) -> io::Result<()> {
+        let pixels: Vec<[u8; 4]> = self
+            .c
+            .iter()
+            .flatten()
+            .map(|&pix| to_rgbe(self.output.apply(pix)))
+            .collect();
+        write_hdr(writeable, self.width, self.height, &pixels)
+    }
+}
+
+fn to_byte(channel: f64) -> u8 {
+    (channel * 255.).round().clamp(0., 255.) as u8
+}
+
+fn to_word(channel: f64) -> u16 {
+    (channel * 65535.).round().clamp(0., 65535.) as u16
 }
 #[cfg(test)]
 mod tests {
-    use crate::{canvas, color};
+    use crate::{
+        canvas, color,
+        hdr::from_rgbe,
+        image_file::parse_image_file,
+        output_transform::{Encoding, OutputTransform, ToneMap},
+        png::{BitDepth, Compression},
+    };
     #[test]
     fn test_canvas_init() {
         let canvas = canvas::Canvas::new(10, 20);
@@ -94,7 +180,7 @@ mod tests {
 
         let mut buf = std::io::BufWriter::new(Vec::new());
 
-        canvas.to_ppm(&mut buf);
+        canvas.to_ppm(&mut buf).expect("Should write ppm");
 
         let bytes = buf.into_inner().expect("Should be able into inner");
         let string_result = String::from_utf8(bytes).expect("Should to UTF 8");
@@ -132,7 +218,7 @@ mod tests {
                 b: 1.,
             },
         );
-        canvas.to_ppm(&mut buf);
+        canvas.to_ppm(&mut buf).expect("Should write ppm");
 
         let bytes = buf.into_inner().expect("Should be able into inner");
         let string_result = String::from_utf8(bytes).expect("Should to UTF 8");
@@ -160,7 +246,7 @@ mod tests {
                 );
             }
         }
-        canvas.to_ppm(&mut buf);
+        canvas.to_ppm(&mut buf).expect("Should write ppm");
         let bytes = buf.into_inner().expect("Should be able into inner");
         let string_result = String::from_utf8(bytes).expect("Should to UTF 8");
         let collection: Vec<&str> = string_result.split("\n").collect();
@@ -171,9 +257,170 @@ mod tests {
         let canvas = canvas::Canvas::new(5, 3);
         let expected = '\n';
         let mut buf = std::io::BufWriter::new(Vec::new());
-        canvas.to_ppm(&mut buf);
+        canvas.to_ppm(&mut buf).expect("Should write ppm");
         let bytes = buf.into_inner().expect("Should be able into inner");
         let string_result = String::from_utf8(bytes).expect("Should to UTF 8");
         assert_eq!(string_result.chars().last(), Some(expected));
     }
+
+    #[test]
+    fn test_canvas_write_ppm_binary() {
+        let mut canvas = canvas::Canvas::new(2, 2);
+        canvas.set_pix((0, 0), color::Color::new(1.5, 0., 0.));
+        canvas.set_pix((1, 1), color::Color::new(0., 0.5, -0.5));
+        let mut buf = Vec::new();
+        canvas.to_ppm_binary(&mut buf).expect("Should write ppm");
+
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend([255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 0]);
+        assert_eq!(buf, expected);
+    }
+
+    struct FullDisk;
+    impl std::io::Write for FullDisk {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::StorageFull,
+                "disk full",
+            ))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_canvas_write_ppm_reports_errors() {
+        let canvas = canvas::Canvas::new(5, 3);
+        let err = canvas.to_ppm(&mut FullDisk).expect_err("Should fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+        let err = canvas
+            .to_ppm_binary(&mut FullDisk)
+            .expect_err("Should fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn test_canvas_write_png() {
+        let mut canvas = canvas::Canvas::new(3, 2);
+        canvas.set_pix((0, 0), color::Color::new(1., 0.5, 0.));
+        canvas.set_pix((2, 1), color::Color::new(0., 0., 2.));
+        let mut buf = Vec::new();
+        canvas
+            .to_png(&mut buf, BitDepth::Eight, Compression::Stored)
+            .expect("Should write png");
+
+        assert_eq!(
+            &buf[0..8],
+            &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]
+        );
+        assert_eq!(&buf[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&buf[16..29], &[0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(&buf[33..41], &[0, 0, 0, 31, b'I', b'D', b'A', b'T']);
+        // zlib header, one final stored block of 2 * (1 + 3 * 3) bytes.
+        assert_eq!(&buf[41..48], &[0x78, 0x01, 1, 20, 0, 0xeb, 0xff]);
+        assert_eq!(&buf[48..52], &[0, 255, 128, 0]);
+        assert_eq!(&buf[58..68], &[0, 0, 0, 0, 0, 0, 0, 0, 0, 255]);
+        assert_eq!(
+            &buf[buf.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn test_canvas_write_png_16_bit() {
+        let mut canvas = canvas::Canvas::new(1, 1);
+        canvas.set_pix((0, 0), color::Color::new(1., 0.5, -1.));
+        let mut buf = Vec::new();
+        canvas
+            .to_png(&mut buf, BitDepth::Sixteen, Compression::Stored)
+            .expect("Should write png");
+        assert_eq!(buf[24], 16);
+        assert_eq!(&buf[48..55], &[0, 0xff, 0xff, 0x80, 0x00, 0, 0]);
+
+        let mut fixed = Vec::new();
+        canvas
+            .to_png(&mut fixed, BitDepth::Sixteen, Compression::Fixed)
+            .expect("Should write png");
+        assert_eq!(&fixed[..33], &buf[..33]);
+    }
+
+    #[test]
+    fn test_canvas_write_pfm_keeps_high_dynamic_range() {
+        let mut canvas = canvas::Canvas::new(3, 2);
+        canvas.set_pix((0, 0), color::Color::new(1.9, 0.5, 0.));
+        canvas.set_pix((2, 1), color::Color::new(-0.25, 1000., 3.5));
+        let mut buf = Vec::new();
+        canvas.to_pfm(&mut buf).expect("Should write pfm");
+
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&buf[..header.len()], header);
+        assert_eq!(buf.len(), header.len() + 3 * 2 * 3 * 4);
+        // The bottom row comes first.
+        assert_eq!(
+            &buf[header.len() + 24..header.len() + 28],
+            &(-0.25f32).to_le_bytes()
+        );
+
+        let read = parse_image_file(&buf).expect("Should parse");
+        assert_eq!(read.shape(), (3, 2));
+        assert_eq!(
+            read.get_pix((0, 0)),
+            color::Color::new(1.9f32 as f64, 0.5, 0.)
+        );
+        assert_eq!(read.get_pix((2, 1)), color::Color::new(-0.25, 1000., 3.5));
+    }
+
+    #[test]
+    fn test_canvas_write_hdr() {
+        let mut canvas = canvas::Canvas::new(2, 1);
+        canvas.set_pix((0, 0), color::Color::new(1.9, 0.5, 0.));
+        canvas.set_pix((1, 0), color::Color::new(8., 8., 8.));
+        let mut buf = Vec::new();
+        canvas.to_hdr(&mut buf).expect("Should write hdr");
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        assert_eq!(&buf[..header.len()], header);
+        let data = &buf[header.len()..];
+        assert_eq!(data.len(), 8);
+        let bright = from_rgbe([data[0], data[1], data[2], data[3]]);
+        assert!((bright.r - 1.9).abs() < 1.9 / 256.);
+        assert_eq!(
+            from_rgbe([data[4], data[5], data[6], data[7]]),
+            color::Color::new(8.03125, 8.03125, 8.03125)
+        );
+    }
+
+    #[test]
+    fn test_canvas_writers_apply_output_transform() {
+        let mut canvas = canvas::Canvas::new(2, 1);
+        canvas.set_pix((0, 0), color::Color::new(0.5, 0.18, 0.));
+        canvas.set_pix((1, 0), color::Color::new(3., 1.9, 1.));
+        assert_eq!(canvas.output_transform(), OutputTransform::default());
+
+        let transform = OutputTransform::new(1., ToneMap::Reinhard, Encoding::Srgb);
+        canvas.set_output_transform(transform);
+        let mut buf = Vec::new();
+        canvas.to_ppm_binary(&mut buf).expect("Should write ppm");
+        let pixels = &buf[buf.len() - 6..];
+        // 0.5 -> 1/3 -> 156, 3 -> 0.75 -> 225, 1.9 -> 0.655 -> 212.
+        assert_eq!(pixels, &[156, 109, 0, 225, 212, 188]);
+
+        let mut text = Vec::new();
+        canvas.to_ppm(&mut text).expect("Should write ppm");
+        let text = String::from_utf8(text).expect("Should be UTF 8");
+        assert!(text.ends_with("156 109 0 225 212 188\n"));
+
+        let mut png = Vec::new();
+        canvas
+            .to_png(&mut png, BitDepth::Eight, Compression::Stored)
+            .expect("Should write png");
+        assert_eq!(&png[49..55], &[156, 109, 0, 225, 212, 188]);
+
+        let mut pfm = Vec::new();
+        canvas.to_pfm(&mut pfm).expect("Should write pfm");
+        let read = parse_image_file(&pfm).expect("Should parse");
+        let expected = transform.apply(canvas.get_pix((1, 0)));
+        assert_eq!(read.get_pix((1, 0)).g, expected.g as f32 as f64);
+    }
 }
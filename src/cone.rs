@@ -0,0 +1,252 @@
+use crate::{
+    bounds::BoundingBox,
+    cylinder::check_cap,
+    intersect::Intersections,
+    intersection::{Intersection, EPSILON},
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    data: ShapeData,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+impl Cone {
+    pub fn new() -> Self {
+        Cone {
+            data: ShapeData::new(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+    pub fn with_mat(material: Material) -> Self {
+        Cone {
+            data: ShapeData::with_mat(material),
+            ..Cone::new()
+        }
+    }
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cone {
+            minimum,
+            maximum,
+            closed,
+            ..Cone::new()
+        }
+    }
+    pub fn truncated_with_mat(
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+        material: Material,
+    ) -> Self {
+        Cone {
+            minimum,
+            maximum,
+            closed,
+            ..Cone::with_mat(material)
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+
+    fn push_within_bounds<'a>(&'a self, ray: &Ray, t: f64, xs: &mut Intersections<'a>) {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+}
+impl Default for Cone {
+    fn default() -> Self {
+        Cone::new()
+    }
+}
+impl Shape for Cone {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Intersections::new();
+        let (o, d) = (ray.origin, ray.direction);
+        let a = d.x * d.x - d.y * d.y + d.z * d.z;
+        let b = 2. * o.x * d.x - 2. * o.y * d.y + 2. * o.z * d.z;
+        let c = o.x * o.x - o.y * o.y + o.z * o.z;
+        if a.abs() < EPSILON {
+            if b.abs() >= EPSILON {
+                self.push_within_bounds(ray, -c / (2. * b), &mut xs);
+            }
+        } else {
+            let disc = b * b - 4. * a * c;
+            if disc < 0. {
+                return xs;
+            }
+            let t0 = (-b - disc.sqrt()) / (2. * a);
+            let t1 = (-b + disc.sqrt()) / (2. * a);
+            self.push_within_bounds(ray, t0, &mut xs);
+            self.push_within_bounds(ray, t1, &mut xs);
+        }
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, object_point: Tuple, _hit: &Intersection) -> Tuple {
+        let dist = object_point.x * object_point.x + object_point.z * object_point.z;
+        if dist < self.maximum * self.maximum && object_point.y >= self.maximum - EPSILON {
+            Tuple::vector(0., 1., 0.)
+        } else if dist < self.minimum * self.minimum && object_point.y <= self.minimum + EPSILON {
+            Tuple::vector(0., -1., 0.)
+        } else {
+            let y = if object_point.y > 0. {
+                -dist.sqrt()
+            } else {
+                dist.sqrt()
+            };
+            Tuple::vector(object_point.x, y, object_point.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Tuple::point(-limit, self.minimum, -limit),
+            Tuple::point(limit, self.maximum, limit),
+        )
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::Intersection, material::Material, object::Shape, ray::Ray, tuple::Tuple,
+    };
+
+    use super::Cone;
+
+    #[test]
+    fn test_ray_intersects_cone() {
+        let shape = Cone::new();
+        let cases = [
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
+            (
+                Tuple::point(0., 0., -5.),
+                Tuple::vector(1., 1., 1.),
+                8.66025,
+                8.66025,
+            ),
+            (
+                Tuple::point(1., 1., -5.),
+                Tuple::vector(-0.5, -1., 1.),
+                4.55006,
+                49.44994,
+            ),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let mut xs = shape.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!((xs.pop().expect("Should have intersection").t - t0).abs() < 0.0001);
+            assert!((xs.pop().expect("Should have intersection").t - t1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_ray_parallel_to_one_half() {
+        let shape = Cone::new();
+        let r = Ray::new(
+            Tuple::point(0., 0., -1.),
+            Tuple::vector(0., 1., 1.).normalize(),
+        );
+        let mut xs = shape.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!((xs.pop().expect("Should have intersection").t - 0.35355).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_intersect_cone_caps() {
+        let shape = Cone::truncated(-0.5, 0.5, true);
+        let cases = [
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.), 0),
+            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 1.), 2),
+            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 0.), 4),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(shape.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_normal_on_cone() {
+        let shape = Cone::new();
+        let cases = [
+            (Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 0.)),
+            (
+                Tuple::point(1., 1., 1.),
+                Tuple::vector(1., -f64::sqrt(2.), 1.),
+            ),
+            (Tuple::point(-1., -1., 0.), Tuple::vector(-1., 1., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(
+                shape.local_normal_at(point, &Intersection::new(0., &shape)),
+                normal
+            );
+        }
+    }
+
+    #[test]
+    fn test_normal_on_cone_caps() {
+        let shape = Cone::truncated(-1., 2., true);
+        assert_eq!(
+            shape.local_normal_at(Tuple::point(0.5, 2., 0.), &Intersection::new(0., &shape)),
+            Tuple::vector(0., 1., 0.)
+        );
+        assert_eq!(
+            shape.local_normal_at(Tuple::point(0.5, -1., 0.), &Intersection::new(0., &shape)),
+            Tuple::vector(0., -1., 0.)
+        );
+    }
+
+    #[test]
+    fn test_cone_with_mat() {
+        let mut mat = Material::default();
+        mat.ambiant = 1.;
+        let shape = Cone::with_mat(mat.clone());
+
+        assert_eq!(*shape.material(), mat);
+        assert_eq!(shape.minimum, f64::NEG_INFINITY);
+        assert_eq!(shape.maximum, f64::INFINITY);
+        assert!(!shape.closed);
+    }
+
+    #[test]
+    fn test_cone_truncated_with_mat() {
+        let mut mat = Material::default();
+        mat.ambiant = 1.;
+        let shape = Cone::truncated_with_mat(1., 2., true, mat.clone());
+
+        assert_eq!(*shape.material(), mat);
+        assert_eq!(shape.minimum, 1.);
+        assert_eq!(shape.maximum, 2.);
+        assert!(shape.closed);
+    }
+}
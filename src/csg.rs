@@ -0,0 +1,246 @@
+use crate::{
+    bounds::{parent_space_bounds, BoundingBox},
+    intersect::{intersect, sorted, Intersections},
+    intersection::Intersection,
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+pub fn intersection_allowed(op: CsgOp, lhit: bool, inl: bool, inr: bool) -> bool {
+    match op {
+        CsgOp::Union => (lhit && !inr) || (!lhit && !inl),
+        CsgOp::Intersection => (lhit && inr) || (!lhit && inl),
+        CsgOp::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+#[derive(Debug)]
+pub struct Csg {
+    data: ShapeData,
+    pub operation: CsgOp,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+}
+impl Csg {
+    pub fn new(operation: CsgOp, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Self {
+        Csg {
+            data: ShapeData::new(),
+            operation,
+            left,
+            right,
+        }
+    }
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    pub fn filter_intersections<'a>(&self, xs: Vec<Intersection<'a>>) -> Vec<Intersection<'a>> {
+        let mut inl = false;
+        let mut inr = false;
+        let mut result = vec![];
+        for i in xs {
+            let lhit = self.left.includes(i.o);
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(i);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+}
+impl Shape for Csg {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+    fn set_material(&mut self, m: Material) {
+        self.left.set_material(m.clone());
+        self.right.set_material(m.clone());
+        self.data.material = m;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        if !self.bounds().intersects(ray) {
+            return Intersections::new();
+        }
+        let mut xs = intersect(ray, self.left.as_ref());
+        xs.extend(intersect(ray, self.right.as_ref()));
+        self.filter_intersections(sorted(xs))
+            .into_iter()
+            .map(|mut i| {
+                i.parents.push(self);
+                i
+            })
+            .collect()
+    }
+
+    fn local_normal_at(&self, _object_point: Tuple, _hit: &Intersection) -> Tuple {
+        unreachable!("CSG normals are computed on the child that was hit")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = parent_space_bounds(self.left.as_ref());
+        b.add_box(&parent_space_bounds(self.right.as_ref()));
+        b
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.left.divide(threshold);
+        self.right.divide(threshold);
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other) || self.left.includes(other) || self.right.includes(other)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cube::Cube,
+        intersect::{hit, intersect, sorted},
+        intersection::{prepare_computations, Intersection},
+        matrix::Matrix,
+        object::Shape,
+        ray::Ray,
+        sphere::Sphere,
+        tuple::Tuple,
+        world::World,
+    };
+
+    use super::{intersection_allowed, Csg, CsgOp};
+
+    #[test]
+    fn test_new_csg() {
+        let c = Csg::new(CsgOp::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+        assert_eq!(c.operation, CsgOp::Union);
+        assert!(c.includes(c.left()));
+        assert!(c.includes(c.right()));
+        assert!(!c.left().includes(c.right()));
+    }
+
+    #[test]
+    fn test_intersection_allowed() {
+        let cases = [
+            (CsgOp::Union, true, true, true, false),
+            (CsgOp::Union, true, true, false, true),
+            (CsgOp::Union, true, false, true, false),
+            (CsgOp::Union, true, false, false, true),
+            (CsgOp::Union, false, true, true, false),
+            (CsgOp::Union, false, true, false, false),
+            (CsgOp::Union, false, false, true, true),
+            (CsgOp::Union, false, false, false, true),
+            (CsgOp::Intersection, true, true, true, true),
+            (CsgOp::Intersection, true, true, false, false),
+            (CsgOp::Intersection, true, false, true, true),
+            (CsgOp::Intersection, true, false, false, false),
+            (CsgOp::Intersection, false, true, true, true),
+            (CsgOp::Intersection, false, true, false, true),
+            (CsgOp::Intersection, false, false, true, false),
+            (CsgOp::Intersection, false, false, false, false),
+            (CsgOp::Difference, true, true, true, false),
+            (CsgOp::Difference, true, true, false, true),
+            (CsgOp::Difference, true, false, true, false),
+            (CsgOp::Difference, true, false, false, true),
+            (CsgOp::Difference, false, true, true, true),
+            (CsgOp::Difference, false, true, false, true),
+            (CsgOp::Difference, false, false, true, false),
+            (CsgOp::Difference, false, false, false, false),
+        ];
+        for (op, lhit, inl, inr, expected) in cases {
+            assert_eq!(intersection_allowed(op, lhit, inl, inr), expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections() {
+        let cases = [
+            (CsgOp::Union, 0, 3),
+            (CsgOp::Intersection, 1, 2),
+            (CsgOp::Difference, 0, 1),
+        ];
+        for (op, x0, x1) in cases {
+            let c = Csg::new(op, Box::new(Sphere::new()), Box::new(Cube::new()));
+            let xs = vec![
+                Intersection::new(1., c.left()),
+                Intersection::new(2., c.right()),
+                Intersection::new(3., c.left()),
+                Intersection::new(4., c.right()),
+            ];
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0], xs[x0]);
+            assert_eq!(result[1], xs[x1]);
+        }
+    }
+
+    #[test]
+    fn test_ray_misses_csg() {
+        let c = Csg::new(CsgOp::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+        let r = Ray::new(Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.));
+        assert_eq!(c.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_ray_hits_csg() {
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0., 0., 0.5))
+            .expect("Transform should be invertible");
+        let c = Csg::new(CsgOp::Union, Box::new(Sphere::new()), Box::new(s2));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = sorted(c.local_intersect(&r));
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.);
+        assert_eq!(xs[0].o, c.left());
+        assert_eq!(xs[1].t, 6.5);
+        assert_eq!(xs[1].o, c.right());
+    }
+
+    #[test]
+    fn test_difference_hollows_out_sphere() {
+        let mut inner = Sphere::new();
+        inner
+            .set_transform(Matrix::scale(0.5, 0.5, 0.5))
+            .expect("Transform should be invertible");
+        let c = Csg::new(CsgOp::Difference, Box::new(Sphere::new()), Box::new(inner));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let ts: Vec<f64> = sorted(intersect(&r, &c)).iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4., 4.5, 5.5, 6.]);
+    }
+
+    #[test]
+    fn test_shading_csg_in_world() {
+        let mut w = World::default_world();
+        let mut carved = Cube::new();
+        carved
+            .set_transform(Matrix::translation(0., 0., -1.5))
+            .expect("Transform should be invertible");
+        let c = Csg::new(CsgOp::Difference, Box::new(Sphere::new()), Box::new(carved));
+        w.objects = vec![Box::new(c)];
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect(&r);
+        let h = hit(&xs).expect("Should hit");
+        assert_eq!(h.t, 4.5);
+
+        let comps = prepare_computations(h, &r, &sorted(xs.clone()));
+        assert_eq!(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::Intersection,
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cube {
+    data: ShapeData,
+}
+impl Cube {
+    pub fn new() -> Self {
+        Cube {
+            data: ShapeData::new(),
+        }
+    }
+    pub fn with_mat(material: Material) -> Self {
+        Cube {
+            data: ShapeData::with_mat(material),
+        }
+    }
+}
+impl Default for Cube {
+    fn default() -> Self {
+        Cube::new()
+    }
+}
+
+pub fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    let tmin = (min - origin) / direction;
+    let tmax = (max - origin) / direction;
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, -1., 1.);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, -1., 1.);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, -1., 1.);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            Intersections::new()
+        } else {
+            Intersections::from([Intersection::new(tmin, self), Intersection::new(tmax, self)])
+        }
+    }
+
+    fn local_normal_at(&self, object_point: Tuple, _hit: &Intersection) -> Tuple {
+        let maxc = object_point
+            .x
+            .abs()
+            .max(object_point.y.abs())
+            .max(object_point.z.abs());
+        if maxc == object_point.x.abs() {
+            Tuple::vector(object_point.x, 0., 0.)
+        } else if maxc == object_point.y.abs() {
+            Tuple::vector(0., object_point.y, 0.)
+        } else {
+            Tuple::vector(0., 0., object_point.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color, intersect::intersect, intersection::Intersection, matrix::Matrix,
+        object::Shape, point_light::Point_Light, ray::Ray, tuple::Tuple, world::World,
+    };
+
+    use super::Cube;
+
+    #[test]
+    fn test_ray_intersects_cube() {
+        let c = Cube::new();
+        let cases = [
+            (
+                Tuple::point(5., 0.5, 0.),
+                Tuple::vector(-1., 0., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(-5., 0.5, 0.),
+                Tuple::vector(1., 0., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 5., 0.),
+                Tuple::vector(0., -1., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, -5., 0.),
+                Tuple::vector(0., 1., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 0., 5.),
+                Tuple::vector(0., 0., -1.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 0., -5.),
+                Tuple::vector(0., 0., 1.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0., 0.5, 0.),
+                Tuple::vector(0., 0., 1.),
+                -1.,
+                1.,
+            ),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let r = Ray::new(origin, direction);
+            let mut xs = c.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs.pop().expect("Should have intersection").t, t1);
+            assert_eq!(xs.pop().expect("Should have intersection").t, t2);
+        }
+    }
+
+    #[test]
+    fn test_ray_misses_cube() {
+        let c = Cube::new();
+        let cases = [
+            (
+                Tuple::point(-2., 0., 0.),
+                Tuple::vector(0.2673, 0.5345, 0.8018),
+            ),
+            (
+                Tuple::point(0., -2., 0.),
+                Tuple::vector(0.8018, 0.2673, 0.5345),
+            ),
+            (
+                Tuple::point(0., 0., -2.),
+                Tuple::vector(0.5345, 0.8018, 0.2673),
+            ),
+            (Tuple::point(2., 0., 2.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(0., 2., 2.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(2., 2., 0.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction);
+            assert_eq!(c.local_intersect(&r).len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_normal_on_cube() {
+        let c = Cube::new();
+        let cases = [
+            (Tuple::point(1., 0.5, -0.8), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(-1., -0.2, 0.9), Tuple::vector(-1., 0., 0.)),
+            (Tuple::point(-0.4, 1., -0.1), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0.3, -1., -0.7), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(-0.6, 0.3, 1.), Tuple::vector(0., 0., 1.)),
+            (Tuple::point(0.4, 0.4, -1.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(1., 1., 1.), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(-1., -1., -1.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(c.local_normal_at(point, &Intersection::new(0., &c)), normal);
+        }
+    }
+
+    #[test]
+    fn test_intersect_transformed_cube() {
+        let mut c = Cube::new();
+        c.set_transform(Matrix::translation(0., 0., 5.) * Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut xs = intersect(&r, &c);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 8.);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 12.);
+    }
+
+    #[test]
+    fn test_cube_casts_shadow() {
+        let mut w = World::new();
+        w.lights.push(Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(0., 10., 0.),
+        ));
+        let mut c = Cube::new();
+        c.set_transform(Matrix::translation(0., 5., 0.))
+            .expect("Transform should be invertible");
+        w.objects.push(Box::new(c));
+        assert!(w.is_shadowed(&Tuple::point(0., 0., 0.), &w.lights[0]));
+        assert!(!w.is_shadowed(&Tuple::point(3., 0., 0.), &w.lights[0]));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::{Intersection, EPSILON},
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    data: ShapeData,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+impl Cylinder {
+    pub fn new() -> Self {
+        Cylinder {
+            data: ShapeData::new(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+    pub fn with_mat(material: Material) -> Self {
+        Cylinder {
+            data: ShapeData::with_mat(material),
+            ..Cylinder::new()
+        }
+    }
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cylinder {
+            minimum,
+            maximum,
+            closed,
+            ..Cylinder::new()
+        }
+    }
+    pub fn truncated_with_mat(
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+        material: Material,
+    ) -> Self {
+        Cylinder {
+            minimum,
+            maximum,
+            closed,
+            ..Cylinder::with_mat(material)
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+impl Default for Cylinder {
+    fn default() -> Self {
+        Cylinder::new()
+    }
+}
+
+pub fn check_cap(ray: &Ray, t: f64, radius: f64) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+    x * x + z * z <= radius * radius + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Intersections::new();
+        let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+        if a.abs() >= EPSILON {
+            let b = 2. * ray.origin.x * ray.direction.x + 2. * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x * ray.origin.x + ray.origin.z * ray.origin.z - 1.;
+            let disc = b * b - 4. * a * c;
+            if disc < 0. {
+                return xs;
+            }
+            let t0 = (-b - disc.sqrt()) / (2. * a);
+            let t1 = (-b + disc.sqrt()) / (2. * a);
+            for t in [t0, t1] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, object_point: Tuple, _hit: &Intersection) -> Tuple {
+        let dist = object_point.x * object_point.x + object_point.z * object_point.z;
+        if dist < 1. && object_point.y >= self.maximum - EPSILON {
+            Tuple::vector(0., 1., 0.)
+        } else if dist < 1. && object_point.y <= self.minimum + EPSILON {
+            Tuple::vector(0., -1., 0.)
+        } else {
+            Tuple::vector(object_point.x, 0., object_point.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(-1., self.minimum, -1.),
+            Tuple::point(1., self.maximum, 1.),
+        )
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::Intersection, material::Material, object::Shape, ray::Ray, tuple::Tuple,
+    };
+
+    use super::Cylinder;
+
+    #[test]
+    fn test_ray_misses_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 0., -5.), Tuple::vector(1., 1., 1.)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_ray_strikes_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 4., 6.),
+            (
+                Tuple::point(0.5, 0., -5.),
+                Tuple::vector(0.1, 1., 1.),
+                6.80798,
+                7.08872,
+            ),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let mut xs = cyl.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!((xs.pop().expect("Should have intersection").t - t0).abs() < 0.0001);
+            assert!((xs.pop().expect("Should have intersection").t - t1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_normal_on_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., 0.), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(0., 5., -1.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(0., -2., 1.), Tuple::vector(0., 0., 1.)),
+            (Tuple::point(-1., 1., 0.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(
+                cyl.local_normal_at(point, &Intersection::new(0., &cyl)),
+                normal
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_cylinder_bounds() {
+        let cyl = Cylinder::new();
+        assert_eq!(cyl.minimum, f64::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f64::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn test_intersect_truncated_cylinder() {
+        let cyl = Cylinder::truncated(1., 2., false);
+        let cases = [
+            (Tuple::point(0., 1.5, 0.), Tuple::vector(0.1, 1., 0.), 0),
+            (Tuple::point(0., 3., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 1., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 1.5, -2.), Tuple::vector(0., 0., 1.), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_intersect_closed_cylinder_caps() {
+        let cyl = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Tuple::point(0., 3., 0.), Tuple::vector(0., -1., 0.), 2),
+            (Tuple::point(0., 3., -2.), Tuple::vector(0., -1., 2.), 2),
+            (Tuple::point(0., 4., -2.), Tuple::vector(0., -1., 1.), 2),
+            (Tuple::point(0., 0., -2.), Tuple::vector(0., 1., 2.), 2),
+            (Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 1.), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_normal_on_cylinder_caps() {
+        let cyl = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0.5, 1., 0.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0., 1., 0.5), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0., 2., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0.5, 2., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 2., 0.5), Tuple::vector(0., 1., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(
+                cyl.local_normal_at(point, &Intersection::new(0., &cyl)),
+                normal
+            );
+        }
+    }
+
+    #[test]
+    fn test_cylinder_with_mat() {
+        let mut mat = Material::default();
+        mat.ambiant = 1.;
+        let cyl = Cylinder::with_mat(mat.clone());
+
+        assert_eq!(*cyl.material(), mat);
+        assert_eq!(cyl.minimum, f64::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f64::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn test_cylinder_truncated_with_mat() {
+        let mut mat = Material::default();
+        mat.ambiant = 1.;
+        let cyl = Cylinder::truncated_with_mat(1., 2., true, mat.clone());
+
+        assert_eq!(*cyl.material(), mat);
+        assert_eq!(cyl.minimum, 1.);
+        assert_eq!(cyl.maximum, 2.);
+        assert!(cyl.closed);
+    }
+}
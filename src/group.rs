@@ -0,0 +1,489 @@
+use std::sync::Arc;
+
+use crate::{
+    bounds::{parent_space_bounds, BoundingBox},
+    intersect::{intersect, Intersections},
+    intersection::Intersection,
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug)]
+pub struct Group {
+    data: ShapeData,
+    children: Vec<Arc<dyn Shape>>,
+    bounds: BoundingBox,
+}
+impl Group {
+    pub fn new() -> Self {
+        Group {
+            data: ShapeData::new(),
+            children: vec![],
+            bounds: BoundingBox::empty(),
+        }
+    }
+    fn with_children(children: Vec<Arc<dyn Shape>>) -> Self {
+        let mut g = Group::new();
+        for child in children {
+            g.add_instance(child);
+        }
+        g
+    }
+    pub fn add_child(&mut self, child: Box<dyn Shape>) {
+        self.add_instance(Arc::from(child));
+    }
+    pub fn add_instance(&mut self, child: Arc<dyn Shape>) {
+        self.bounds.add_box(&parent_space_bounds(child.as_ref()));
+        self.children.push(child);
+    }
+    pub fn children(&self) -> &[Arc<dyn Shape>] {
+        &self.children
+    }
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn recompute_bounds(&mut self) {
+        self.bounds = BoundingBox::empty();
+        for child in &self.children {
+            self.bounds.add_box(&parent_space_bounds(child.as_ref()));
+        }
+    }
+
+    // Surface area heuristic: try every split along each axis of the children
+    // sorted by centroid, and keep the cheapest one if it beats not splitting.
+    fn split_children(&self) -> Option<(Vec<usize>, Vec<usize>)> {
+        let n = self.children.len();
+        let boxes: Vec<BoundingBox> = self
+            .children
+            .iter()
+            .map(|child| parent_space_bounds(child.as_ref()))
+            .collect();
+        let sorted_on = |axis: usize| {
+            let centroid = |b: &BoundingBox| {
+                let c = b.centroid();
+                [c.x, c.y, c.z][axis]
+            };
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by(|&a, &b| centroid(&boxes[a]).total_cmp(&centroid(&boxes[b])));
+            order
+        };
+        let mut best_cost = n as f64 * self.bounds.surface_area();
+        let mut best = None;
+        for axis in 0..3 {
+            let order = sorted_on(axis);
+
+            let mut left_areas = Vec::with_capacity(n);
+            let mut left = BoundingBox::empty();
+            for &i in &order {
+                left.add_box(&boxes[i]);
+                left_areas.push(left.surface_area());
+            }
+            let mut right = BoundingBox::empty();
+            for split in (1..n).rev() {
+                right.add_box(&boxes[order[split]]);
+                let cost = left_areas[split - 1] * split as f64
+                    + right.surface_area() * (n - split) as f64;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = Some((axis, split));
+                }
+            }
+        }
+        best.map(|(axis, split)| {
+            let mut left = sorted_on(axis);
+            let right = left.split_off(split);
+            (left, right)
+        })
+    }
+}
+impl Default for Group {
+    fn default() -> Self {
+        Group::new()
+    }
+}
+impl Shape for Group {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+    fn set_material(&mut self, m: Material) {
+        // Instances shared with other groups through add_instance keep their
+        // own material, as changing it would also change every other copy.
+        for child in self.children.iter_mut() {
+            if let Some(child) = Arc::get_mut(child) {
+                child.set_material(m.clone());
+            }
+        }
+        self.data.material = m;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        if !self.bounds.intersects(ray) {
+            return Intersections::new();
+        }
+        self.children
+            .iter()
+            .flat_map(|child| intersect(ray, child.as_ref()))
+            .map(|mut i| {
+                i.parents.push(self);
+                i
+            })
+            .collect()
+    }
+
+    fn local_normal_at(&self, _object_point: Tuple, _hit: &Intersection) -> Tuple {
+        unreachable!("Group normals are computed on the child that was hit")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        if self.children.len() > threshold {
+            if let Some((left, right)) = self.split_children() {
+                let mut children: Vec<Option<Arc<dyn Shape>>> =
+                    self.children.drain(..).map(Some).collect();
+                for part in [left, right] {
+                    let mut part: Vec<Arc<dyn Shape>> = part
+                        .into_iter()
+                        .filter_map(|i| children[i].take())
+                        .collect();
+                    if part.len() == 1 {
+                        self.children.append(&mut part);
+                    } else {
+                        self.children.push(Arc::new(Group::with_children(part)));
+                    }
+                }
+            }
+        }
+        // Shared instances are not divided either; that needs the only handle.
+        for child in self.children.iter_mut() {
+            if let Some(child) = Arc::get_mut(child) {
+                child.divide(threshold);
+            }
+        }
+        self.recompute_bounds();
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other) || self.children.iter().any(|child| child.includes(other))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use crate::{
+        bounds::BoundingBox,
+        intersect::{hit, intersect, sorted},
+        intersection::{prepare_computations, Intersection},
+        material::Material,
+        matrix::Matrix,
+        object::{normal_at, normal_to_world, world_to_object, Shape},
+        ray::Ray,
+        sphere::Sphere,
+        triangle::Triangle,
+        tuple::{assert_tuple_eq, Tuple},
+    };
+
+    use super::Group;
+
+    fn as_group(s: &dyn Shape) -> &Group {
+        (s as &dyn Any)
+            .downcast_ref::<Group>()
+            .expect("Should be a group")
+    }
+
+    fn spheres_at(xs: &[f64]) -> Group {
+        let mut g = Group::new();
+        for &x in xs {
+            let mut s = Sphere::new();
+            s.set_transform(Matrix::translation(x, 0., 0.))
+                .expect("Transform should be invertible");
+            g.add_child(Box::new(s));
+        }
+        g
+    }
+
+    fn nested() -> (Group, Group, Sphere) {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix::rot_y(PI / 2.))
+            .expect("Transform should be invertible");
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix::scale(1., 2., 3.))
+            .expect("Transform should be invertible");
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5., 0., 0.))
+            .expect("Transform should be invertible");
+        (g1, g2, s)
+    }
+
+    #[test]
+    fn test_new_group() {
+        let g = Group::new();
+        assert_eq!(*g.transform(), Matrix::identity());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn test_add_child() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        assert_eq!(g.children().len(), 1);
+    }
+
+    #[test]
+    fn test_intersect_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        assert_eq!(g.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_intersect_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0., 0., -3.))
+            .expect("Transform should be invertible");
+        let mut s3 = Sphere::new();
+        s3.set_transform(Matrix::translation(5., 0., 0.))
+            .expect("Transform should be invertible");
+        g.add_child(Box::new(s1));
+        g.add_child(Box::new(s2));
+        g.add_child(Box::new(s3));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = g.local_intersect(&r).into_sorted_vec();
+        let children = g.children();
+
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[3].o, children[1].as_ref());
+        assert_eq!(xs[2].o, children[1].as_ref());
+        assert_eq!(xs[1].o, children[0].as_ref());
+        assert_eq!(xs[0].o, children[0].as_ref());
+        assert_eq!(xs[0].parents, vec![&g as &dyn Shape]);
+    }
+
+    #[test]
+    fn test_intersect_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5., 0., 0.))
+            .expect("Transform should be invertible");
+        g.add_child(Box::new(s));
+        let r = Ray::new(Tuple::point(10., 0., -10.), Tuple::vector(0., 0., 1.));
+        assert_eq!(intersect(&r, &g).len(), 2);
+    }
+
+    #[test]
+    fn test_set_material_propagates() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        let mut m = Material::default();
+        m.ambiant = 1.;
+        g.set_material(m.clone());
+        assert_eq!(*g.children()[0].material(), m);
+    }
+
+    #[test]
+    fn test_world_to_object_through_parents() {
+        let (g1, mut g2, s) = nested();
+        g2.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        let p = world_to_object(&s, &[&g2, &g1], Tuple::point(-2., 0., -10.));
+        assert_tuple_eq(p, Tuple::point(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_normal_to_world_through_parents() {
+        let (g1, g2, s) = nested();
+        let v = f64::sqrt(3.) / 3.;
+        let n = normal_to_world(&s, &[&g2, &g1], Tuple::vector(v, v, v));
+        assert_tuple_eq(n, Tuple::vector(0.2857, 0.4286, -0.8571));
+    }
+
+    #[test]
+    fn test_normal_on_child_object() {
+        let (g1, g2, s) = nested();
+        let mut i = Intersection::new(0., &s);
+        i.parents = vec![&g2, &g1];
+        let n = normal_at(&s, Tuple::point(1.7321, 1.1547, -5.5774), &i);
+        assert_tuple_eq(n, Tuple::vector(0.2857, 0.4286, -0.8571));
+    }
+
+    #[test]
+    fn test_hit_in_nested_groups_records_parents() {
+        let (mut g1, mut g2, s) = nested();
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+        let r = Ray::new(Tuple::point(0., 0., 10.), Tuple::vector(0., 0., -1.));
+        let xs = intersect(&r, &g1);
+        let h = hit(&xs).expect("Should hit");
+        let g2 = g1.children()[0].as_ref();
+
+        assert_eq!(h.parents, vec![g2, &g1 as &dyn Shape]);
+        let comps = prepare_computations(h, &r, &sorted(xs.clone()));
+        assert_tuple_eq(comps.point, Tuple::point(0., 0., -4.));
+        assert_tuple_eq(comps.normalv, Tuple::vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_instanced_child_in_two_groups() {
+        let shared: Arc<dyn Shape> = Arc::new(Sphere::new());
+        let mut left = Group::new();
+        left.set_transform(Matrix::translation(-3., 0., 0.))
+            .expect("Transform should be invertible");
+        left.add_instance(shared.clone());
+        let mut right = Group::new();
+        right
+            .set_transform(Matrix::translation(3., 0., 0.) * Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        right.add_instance(shared.clone());
+        let mut scene = Group::new();
+        scene.add_child(Box::new(left));
+        scene.add_child(Box::new(right));
+
+        let r = Ray::new(Tuple::point(-3., 0., -10.), Tuple::vector(0., 0., 1.));
+        let xs = intersect(&r, &scene);
+        let h = hit(&xs).expect("Should hit left instance");
+        assert_eq!(h.t, 9.);
+        assert_eq!(h.o, shared.as_ref());
+
+        let r = Ray::new(Tuple::point(3., 0., -10.), Tuple::vector(0., 0., 1.));
+        let xs = intersect(&r, &scene);
+        let h = hit(&xs).expect("Should hit right instance");
+        assert_eq!(h.t, 8.);
+        assert_eq!(h.o, shared.as_ref());
+        let comps = prepare_computations(h, &r, &sorted(xs.clone()));
+        assert_tuple_eq(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_shared_instances_are_left_untouched() {
+        let shared: Arc<dyn Shape> = Arc::new(spheres_at(&[-6., -2., 2., 6.]));
+        let mut g = Group::new();
+        g.add_instance(shared.clone());
+        g.add_child(Box::new(Sphere::new()));
+        let mut m = Material::default();
+        m.ambiant = 1.;
+        g.set_material(m.clone());
+        g.divide(1);
+
+        assert_eq!(*g.children()[1].material(), m);
+        let instance = as_group(g.children()[0].as_ref());
+        assert_eq!(instance.children().len(), 4);
+        assert_eq!(*instance.children()[0].material(), Material::default());
+
+        // Once the group holds the only handle, the child is divided as usual.
+        drop(shared);
+        g.divide(1);
+        assert_eq!(as_group(g.children()[0].as_ref()).children().len(), 2);
+    }
+
+    #[test]
+    fn test_includes_children() {
+        let mut inner = Group::new();
+        inner.add_child(Box::new(Sphere::new()));
+        let mut g = Group::new();
+        g.add_child(Box::new(inner));
+        let inner = (g.children()[0].as_ref() as &dyn Any)
+            .downcast_ref::<Group>()
+            .expect("Should be a group");
+        let other = Sphere::new();
+
+        assert!(g.includes(&g));
+        assert!(g.includes(inner.children()[0].as_ref()));
+        assert!(!g.includes(&other));
+    }
+
+    #[test]
+    fn test_ray_misses_group_bounds() {
+        let g = spheres_at(&[-2., 2.]);
+        assert_eq!(
+            g.bounds(),
+            BoundingBox::new(Tuple::point(-3., -1., -1.), Tuple::point(3., 1., 1.))
+        );
+        let r = Ray::new(Tuple::point(0., 5., -5.), Tuple::vector(0., 0., 1.));
+        assert_eq!(g.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_divide_splits_separated_children() {
+        let mut g = spheres_at(&[-6., -2., 2., 6.]);
+        let bounds = g.bounds();
+        g.divide(1);
+
+        assert_eq!(g.bounds(), bounds);
+        assert_eq!(g.children().len(), 2);
+        let left = as_group(g.children()[0].as_ref());
+        let right = as_group(g.children()[1].as_ref());
+        assert_eq!(left.children().len(), 2);
+        assert_eq!(right.children().len(), 2);
+        assert_eq!(left.bounds().max.x, -1.);
+        assert_eq!(right.bounds().min.x, 1.);
+    }
+
+    #[test]
+    fn test_divide_keeps_overlapping_children() {
+        let mut g = spheres_at(&[0., 0., 0.]);
+        g.divide(1);
+        assert_eq!(g.children().len(), 3);
+
+        let mut g = spheres_at(&[-6., 6.]);
+        g.divide(2);
+        assert_eq!(g.children().len(), 2);
+        assert!(g
+            .children()
+            .iter()
+            .all(|c| (c.as_ref() as &dyn Any).is::<Sphere>()));
+    }
+
+    #[test]
+    fn test_divide_large_mesh_preserves_hits() {
+        let mut flat = Group::new();
+        let mut tree = Group::new();
+        let size = 30;
+        for i in 0..size {
+            for j in 0..size {
+                let (x, z) = (i as f64, j as f64);
+                let p1 = Tuple::point(x, (x * 0.3).sin(), z);
+                let p2 = Tuple::point(x + 1., ((x + 1.) * 0.3).sin(), z);
+                let p3 = Tuple::point(x, (x * 0.3).sin(), z + 1.);
+                flat.add_child(Box::new(Triangle::new(p1, p2, p3)));
+                tree.add_child(Box::new(Triangle::new(p1, p2, p3)));
+            }
+        }
+        tree.divide(4);
+        assert!(tree.children().len() < 10);
+
+        let mut hits = 0;
+        for k in 0..20 {
+            let k = k as f64;
+            let r = Ray::new(
+                Tuple::point(k * 1.17 + 0.3, 5., k * 0.91 + 0.2),
+                Tuple::vector(0.1, -1., 0.05).normalize(),
+            );
+            let expected: Vec<f64> = sorted(flat.local_intersect(&r))
+                .iter()
+                .map(|i| i.t)
+                .collect();
+            let actual: Vec<f64> = sorted(tree.local_intersect(&r))
+                .iter()
+                .map(|i| i.t)
+                .collect();
+            hits += expected.len();
+            assert_eq!(actual, expected);
+        }
+        assert!(hits > 0);
+    }
+}
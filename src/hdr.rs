@@ -0,0 +1,221 @@
+use std::io::{self, Write};
+
+use crate::color::Color;
+
+const MIN_RUN: usize = 4;
+const MAX_RUN: usize = 127;
+const MAX_LITERAL: usize = 128;
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+
+pub fn to_rgbe(color: Color) -> [u8; 4] {
+    // The largest value an 8 bit exponent can hold is just under 2^127.
+    let max_value = 255. / 256. * 2f64.powi(127);
+    let channels = [color.r, color.g, color.b].map(|c| {
+        if c.is_nan() {
+            0.
+        } else {
+            c.clamp(0., max_value)
+        }
+    });
+    let v = channels.iter().copied().fold(0., f64::max);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    let mut exponent = v.log2().floor() as i32 + 1;
+    // log2 can land on the wrong side of a power of two.
+    let mantissa = v / 2f64.powi(exponent);
+    if mantissa >= 1. {
+        exponent += 1;
+    } else if mantissa < 0.5 {
+        exponent -= 1;
+    }
+    let scale = 256. / 2f64.powi(exponent);
+    let [r, g, b] = channels.map(|c| (c * scale) as u8);
+    [r, g, b, (exponent + 128) as u8]
+}
+
+pub fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::new(0., 0., 0.);
+    }
+    let f = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * f,
+        (rgbe[1] as f64 + 0.5) * f,
+        (rgbe[2] as f64 + 0.5) * f,
+    )
+}
+
+fn encode_component(data: &[u8], out: &mut Vec<u8>) {
+    let n = data.len();
+    let mut pos = 0;
+    while pos < n {
+        let mut run_start = pos;
+        let mut run_len = 0;
+        while run_start < n {
+            run_len = 1;
+            while run_start + run_len < n
+                && run_len < MAX_RUN
+                && data[run_start + run_len] == data[run_start]
+            {
+                run_len += 1;
+            }
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+        }
+        while pos < run_start {
+            let len = (run_start - pos).min(MAX_LITERAL);
+            out.push(len as u8);
+            out.extend(&data[pos..pos + len]);
+            pos += len;
+        }
+        if run_start < n {
+            out.push((128 + run_len) as u8);
+            out.push(data[run_start]);
+            pos = run_start + run_len;
+        }
+    }
+}
+
+pub fn encode_scanline(pixels: &[[u8; 4]], out: &mut Vec<u8>) {
+    let width = pixels.len();
+    if !(MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width) {
+        // Run-length encoding is only defined for these widths.
+        for pix in pixels {
+            out.extend(pix);
+        }
+        return;
+    }
+    out.extend([2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+    let mut component = Vec::with_capacity(width);
+    for i in 0..4 {
+        component.clear();
+        component.extend(pixels.iter().map(|pix| pix[i]));
+        encode_component(&component, out);
+    }
+}
+
+pub fn write_hdr<T: Write>(
+    writeable: &mut T,
+    width: usize,
+    height: usize,
+    pixels: &[[u8; 4]],
+) -> io::Result<()> {
+    write!(
+        writeable,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {height} +X {width}\n"
+    )?;
+    let mut scanline = Vec::new();
+    for row in pixels.chunks(width.max(1)) {
+        scanline.clear();
+        encode_scanline(row, &mut scanline);
+        writeable.write_all(&scanline)?;
+    }
+    writeable.flush()
+}
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::{encode_scanline, from_rgbe, to_rgbe, write_hdr};
+
+    fn decode_scanline(data: &[u8], width: usize) -> (Vec<[u8; 4]>, usize) {
+        let mut pixels = vec![[0; 4]; width];
+        if data[0] != 2 || data[1] != 2 {
+            for (i, pix) in pixels.iter_mut().enumerate() {
+                pix.copy_from_slice(&data[i * 4..i * 4 + 4]);
+            }
+            return (pixels, width * 4);
+        }
+        assert_eq!(data[2] as usize * 256 + data[3] as usize, width);
+        let mut pos = 4;
+        for i in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = data[pos] as usize;
+                if count > 128 {
+                    for pix in &mut pixels[x..x + count - 128] {
+                        pix[i] = data[pos + 1];
+                    }
+                    x += count - 128;
+                    pos += 2;
+                } else {
+                    for (j, pix) in pixels[x..x + count].iter_mut().enumerate() {
+                        pix[i] = data[pos + 1 + j];
+                    }
+                    x += count;
+                    pos += 1 + count;
+                }
+            }
+        }
+        (pixels, pos)
+    }
+
+    #[test]
+    fn test_rgbe_conversion() {
+        assert_eq!(to_rgbe(Color::new(1., 1., 1.)), [128, 128, 128, 129]);
+        assert_eq!(to_rgbe(Color::new(0.5, 0.25, 0.)), [128, 64, 0, 128]);
+        assert_eq!(to_rgbe(Color::new(0., 0., 0.)), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(Color::new(-1., f64::NAN, 2.)), [0, 0, 128, 130]);
+        assert_eq!(from_rgbe([0, 0, 0, 0]), Color::new(0., 0., 0.));
+
+        for color in [
+            Color::new(1.9, 1.9, 1.9),
+            Color::new(0.1, 42., 3.5),
+            Color::new(1000., 0.001, 7.),
+        ] {
+            let back = from_rgbe(to_rgbe(color));
+            let max = color.r.max(color.g).max(color.b);
+            for (a, b) in [(color.r, back.r), (color.g, back.g), (color.b, back.b)] {
+                assert!((a - b).abs() <= max / 256.);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_scanline_runs_and_literals() {
+        let mut pixels = vec![[10, 20, 30, 128]; 200];
+        for (i, pix) in pixels.iter_mut().enumerate().skip(150) {
+            pix[0] = i as u8;
+        }
+        let mut out = vec![];
+        encode_scanline(&pixels, &mut out);
+        assert_eq!(&out[0..4], &[2, 2, 0, 200]);
+        // Red: a maximal run, a shorter run, then 50 literals.
+        assert_eq!(&out[4..8], &[255, 10, 128 + 23, 10]);
+        assert_eq!(out[8], 50);
+        assert!(out.len() < pixels.len() * 4);
+
+        let (decoded, used) = decode_scanline(&out, pixels.len());
+        assert_eq!(decoded, pixels);
+        assert_eq!(used, out.len());
+    }
+
+    #[test]
+    fn test_encode_short_scanline_is_flat() {
+        let pixels = vec![[1, 2, 3, 4], [5, 6, 7, 8]];
+        let mut out = vec![];
+        encode_scanline(&pixels, &mut out);
+        assert_eq!(out, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_write_hdr() {
+        let width = 9;
+        let pixels: Vec<[u8; 4]> = (0..width * 2)
+            .map(|i| to_rgbe(Color::new(i as f64 * 0.5, 1.9, 0.)))
+            .collect();
+        let mut buf = vec![];
+        write_hdr(&mut buf, width, 2, &pixels).expect("Should write hdr");
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 9\n";
+        assert_eq!(&buf[..header.len()], header);
+        let (first, used) = decode_scanline(&buf[header.len()..], width);
+        let (second, _) = decode_scanline(&buf[header.len() + used..], width);
+        assert_eq!(first, &pixels[..width]);
+        assert_eq!(second, &pixels[width..]);
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{canvas::Canvas, color::Color};
+
+#[derive(Debug, PartialEq)]
+pub struct ImageError {
+    pub offset: usize,
+    pub message: String,
+}
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.message)
+    }
+}
+impl std::error::Error for ImageError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    PlainGray,
+    PlainColor,
+    RawGray,
+    RawColor,
+    FloatGray,
+    FloatColor,
+}
+impl Format {
+    fn channels(self) -> usize {
+        match self {
+            Format::PlainGray | Format::RawGray | Format::FloatGray => 1,
+            Format::PlainColor | Format::RawColor | Format::FloatColor => 3,
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+impl Reader<'_> {
+    fn error(&self, message: String) -> ImageError {
+        ImageError {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self, what: &str) -> Result<&str, ImageError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.data.len() && !self.data[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error(format!("unexpected end of data, expected {what}")));
+        }
+        std::str::from_utf8(&self.data[start..self.pos]).map_err(|_| ImageError {
+            offset: start,
+            message: format!("invalid {what}"),
+        })
+    }
+
+    fn number<T: FromStr>(&mut self, what: &str) -> Result<T, ImageError> {
+        let start = self.pos;
+        let token = self.token(what)?;
+        token.parse().map_err(|_| ImageError {
+            offset: start,
+            message: format!("invalid {what} '{token}'"),
+        })
+    }
+
+    fn end_of_header(&mut self) -> Result<(), ImageError> {
+        match self.data.get(self.pos) {
+            Some(c) if c.is_ascii_whitespace() => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error("expected whitespace after header".to_string())),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&[u8], ImageError> {
+        if count > self.remaining() {
+            return Err(self.error(format!(
+                "unexpected end of data, expected {count} more bytes"
+            )));
+        }
+        let bytes = &self.data[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(bytes)
+    }
+}
+
+fn read_integer_samples(
+    r: &mut Reader,
+    format: Format,
+    count: usize,
+) -> Result<Vec<f64>, ImageError> {
+    let maxval: u32 = r.number("maxval")?;
+    if maxval == 0 || maxval > 65535 {
+        return Err(r.error(format!("maxval {maxval} out of range (1..=65535)")));
+    }
+    if format == Format::PlainGray || format == Format::PlainColor {
+        // Every plain sample takes at least one byte, so a bogus header cannot
+        // make us reserve more than the input could ever fill.
+        let mut samples = Vec::with_capacity(count.min(r.remaining()));
+        for _ in 0..count {
+            let start = r.pos;
+            let sample: u32 = r.number("sample")?;
+            if sample > maxval {
+                return Err(ImageError {
+                    offset: start,
+                    message: format!("sample {sample} exceeds maxval {maxval}"),
+                });
+            }
+            samples.push(sample as f64 / maxval as f64);
+        }
+        Ok(samples)
+    } else {
+        r.end_of_header()?;
+        let width = if maxval < 256 { 1 } else { 2 };
+        let len = count
+            .checked_mul(width)
+            .ok_or_else(|| r.error("image data size overflows".to_string()))?;
+        let start = r.pos;
+        let bytes = r.bytes(len)?;
+        bytes
+            .chunks(width)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let sample = chunk.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+                if sample > maxval {
+                    return Err(ImageError {
+                        offset: start + i * width,
+                        message: format!("sample {sample} exceeds maxval {maxval}"),
+                    });
+                }
+                Ok(sample as f64 / maxval as f64)
+            })
+            .collect()
+    }
+}
+
+fn read_float_samples(
+    r: &mut Reader,
+    row_len: usize,
+    count: usize,
+) -> Result<Vec<f64>, ImageError> {
+    let scale: f64 = r.number("scale")?;
+    if scale == 0. || !scale.is_finite() {
+        return Err(r.error(format!("invalid scale {scale}")));
+    }
+    r.end_of_header()?;
+    let len = count
+        .checked_mul(4)
+        .ok_or_else(|| r.error("image data size overflows".to_string()))?;
+    let bytes = r.bytes(len)?;
+    let values: Vec<f64> = bytes
+        .chunks(4)
+        .map(|chunk| {
+            let raw = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            let v = if scale < 0. {
+                f32::from_le_bytes(raw)
+            } else {
+                f32::from_be_bytes(raw)
+            };
+            v as f64 * scale.abs()
+        })
+        .collect();
+    // PFM scanlines run from the bottom of the image to the top.
+    Ok(values
+        .chunks(row_len.max(1))
+        .rev()
+        .flatten()
+        .copied()
+        .collect())
+}
+
+pub fn parse_image_file(data: &[u8]) -> Result<Canvas, ImageError> {
+    let mut r = Reader { data, pos: 0 };
+    let format = match r.token("magic number")? {
+        "P2" => Format::PlainGray,
+        "P3" => Format::PlainColor,
+        "P5" => Format::RawGray,
+        "P6" => Format::RawColor,
+        "Pf" => Format::FloatGray,
+        "PF" => Format::FloatColor,
+        magic => {
+            return Err(ImageError {
+                offset: 0,
+                message: format!("unsupported magic number '{magic}'"),
+            })
+        }
+    };
+    let width: usize = r.number("width")?;
+    let height: usize = r.number("height")?;
+    if width == 0 || height == 0 {
+        return Err(r.error(format!("image size {width}x{height} has no pixels")));
+    }
+    let channels = format.channels();
+    let count = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(channels))
+        .ok_or_else(|| r.error(format!("image size {width}x{height} overflows")))?;
+    let samples = match format {
+        Format::FloatGray | Format::FloatColor => {
+            read_float_samples(&mut r, width * channels, count)?
+        }
+        _ => read_integer_samples(&mut r, format, count)?,
+    };
+
+    let colors: Vec<Color> = match format {
+        Format::PlainGray | Format::RawGray | Format::FloatGray => {
+            samples.iter().map(|&v| Color::new(v, v, v)).collect()
+        }
+        Format::PlainColor | Format::RawColor | Format::FloatColor => samples
+            .chunks_exact(3)
+            .map(|pix| Color::new(pix[0], pix[1], pix[2]))
+            .collect(),
+    };
+    let mut canvas = Canvas::new(width, height);
+    for (i, color) in colors.into_iter().enumerate() {
+        canvas.set_pix((i % width, i / width), color);
+    }
+    Ok(canvas)
+}
+#[cfg(test)]
+mod tests {
+    use crate::{canvas::Canvas, color::Color};
+
+    use super::{parse_image_file, ImageError};
+
+    #[test]
+    fn test_read_p3() {
+        let file =
+            "P3\n# a comment\n2 2 # trailing comment\n255\n255 0 0  0 255 0\n0 0 255 255 255 255\n";
+        let canvas = parse_image_file(file.as_bytes()).expect("Should parse");
+        assert_eq!(canvas.shape(), (2, 2));
+        assert_eq!(canvas.get_pix((0, 0)), Color::new(1., 0., 0.));
+        assert_eq!(canvas.get_pix((1, 0)), Color::new(0., 1., 0.));
+        assert_eq!(canvas.get_pix((0, 1)), Color::new(0., 0., 1.));
+        assert_eq!(canvas.get_pix((1, 1)), Color::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn test_read_arbitrary_maxval() {
+        let canvas = parse_image_file(b"P3 1 1 100 50 25 100").expect("Should parse");
+        assert_eq!(canvas.get_pix((0, 0)), Color::new(0.5, 0.25, 1.));
+
+        let canvas = parse_image_file(b"P2\n2 1\n4\n0 2\n").expect("Should parse");
+        assert_eq!(canvas.get_pix((0, 0)), Color::new(0., 0., 0.));
+        assert_eq!(canvas.get_pix((1, 0)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_read_p6_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.set_pix((0, 0), Color::new(1., 0.2, 0.));
+        canvas.set_pix((2, 1), Color::new(0., 0.6, 1.));
+        let mut buf = Vec::new();
+        canvas.to_ppm_binary(&mut buf).expect("Should write ppm");
+        let read = parse_image_file(&buf).expect("Should parse");
+        assert_eq!(read.shape(), (3, 2));
+        assert_eq!(read.get_pix((0, 0)), Color::new(1., 0.2, 0.));
+        assert_eq!(read.get_pix((2, 1)), Color::new(0., 0.6, 1.));
+
+        let mut buf = Vec::new();
+        canvas.to_ppm(&mut buf).expect("Should write ppm");
+        let read = parse_image_file(&buf).expect("Should parse");
+        assert_eq!(read.get_pix((2, 1)), Color::new(0., 0.6, 1.));
+    }
+
+    #[test]
+    fn test_read_16_bit_p5() {
+        let mut file = b"P5\n2 1\n65535\n".to_vec();
+        file.extend([0xff, 0xff, 0x80, 0x00]);
+        let canvas = parse_image_file(&file).expect("Should parse");
+        assert_eq!(canvas.get_pix((0, 0)), Color::new(1., 1., 1.));
+        let v = 0x8000 as f64 / 65535.;
+        assert_eq!(canvas.get_pix((1, 0)), Color::new(v, v, v));
+    }
+
+    #[test]
+    fn test_read_pfm_endianness_and_row_order() {
+        let mut little = b"PF\n1 2\n-1.0\n".to_vec();
+        let mut big = b"PF\n1 2\n1.0\n".to_vec();
+        for v in [0.25f32, 0.5, 2., 1., 0., -1.] {
+            little.extend(v.to_le_bytes());
+            big.extend(v.to_be_bytes());
+        }
+        for file in [little, big] {
+            let canvas = parse_image_file(&file).expect("Should parse");
+            assert_eq!(canvas.get_pix((0, 0)), Color::new(1., 0., -1.));
+            assert_eq!(canvas.get_pix((0, 1)), Color::new(0.25, 0.5, 2.));
+        }
+
+        let mut gray = b"Pf 2 1 -2\n".to_vec();
+        for v in [0.5f32, 1.5] {
+            gray.extend(v.to_le_bytes());
+        }
+        let canvas = parse_image_file(&gray).expect("Should parse");
+        assert_eq!(canvas.get_pix((1, 0)), Color::new(3., 3., 3.));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_image_file(b"P7\n1 1\n255\n").expect_err("Should fail");
+        assert_eq!(err.to_string(), "byte 0: unsupported magic number 'P7'");
+
+        let err = parse_image_file(b"P3\n1 x\n255\n").expect_err("Should fail");
+        assert_eq!(
+            err,
+            ImageError {
+                offset: 4,
+                message: "invalid height 'x'".to_string()
+            }
+        );
+
+        let err = parse_image_file(b"P3 1 1 255 1 2 300").expect_err("Should fail");
+        assert_eq!(err.message, "sample 300 exceeds maxval 255");
+
+        let err = parse_image_file(b"P3 1 1 0").expect_err("Should fail");
+        assert_eq!(err.message, "maxval 0 out of range (1..=65535)");
+
+        let err = parse_image_file(b"P6 2 2 255\n\x01\x02").expect_err("Should fail");
+        assert_eq!(
+            err.message,
+            "unexpected end of data, expected 12 more bytes"
+        );
+
+        let err = parse_image_file(b"P3 2 1 255 1 2 3").expect_err("Should fail");
+        assert_eq!(err.message, "unexpected end of data, expected sample");
+
+        let err = parse_image_file(b"P5 2 1 100\n\x32\x65").expect_err("Should fail");
+        assert_eq!(
+            err,
+            ImageError {
+                offset: 12,
+                message: "sample 101 exceeds maxval 100".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_huge_dimensions() {
+        // The header claims far more samples than the data holds.
+        let err =
+            parse_image_file(b"P3\n100000000 100000000\n255\n1 2 3\n").expect_err("Should fail");
+        assert_eq!(err.message, "unexpected end of data, expected sample");
+
+        for file in [
+            "P6 4294967296 4294967296 255\n",
+            "PF 4294967296 4294967296 -1\n",
+        ] {
+            let err = parse_image_file(file.as_bytes()).expect_err("Should fail");
+            assert_eq!(err.message, "image size 4294967296x4294967296 overflows");
+        }
+
+        for file in [
+            "P6 1 3074457345618258603 65535\n",
+            "Pf 1 4611686018427387904 -1\n",
+        ] {
+            let err = parse_image_file(file.as_bytes()).expect_err("Should fail");
+            assert_eq!(err.message, "image data size overflows");
+        }
+
+        // A zero dimension must not let the other one through unchecked.
+        for file in ["P6\n100000000000 0\n255\n", "P3\n0 100000000000\n255\n"] {
+            let err = parse_image_file(file.as_bytes()).expect_err("Should fail");
+            assert!(err.message.ends_with("has no pixels"), "{}", err.message);
+        }
+    }
+}
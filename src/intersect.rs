@@ -1,35 +1,24 @@
-use crate::intersection;
 use crate::intersection::Intersection;
-use crate::object::Object;
+use crate::object::Shape;
 use crate::ray;
-use crate::tuple;
 use std::collections::BinaryHeap;
 
-type Intersections = BinaryHeap<Intersection>;
-pub fn intersect(r: &ray::Ray, s: &Object) -> Intersections {
-    let ray = match s {
-        Object::Sphere(m, _) => r.transform(&m.inverse().expect("Should be inversable")),
-    };
-    let sphere_to_ray = ray.origin - tuple::Tuple::point(0., 0., 0.);
-    let a = ray.direction.dot(&ray.direction);
-    let b = 2. * ray.direction.dot(&sphere_to_ray);
-    let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
-
-    let discriminant = b * b - 4.0 * a * c;
-    if discriminant < 0. {
-        BinaryHeap::new()
-    } else {
-        BinaryHeap::from([
-            intersection::Intersection::new((-b - f64::sqrt(discriminant)) / (2.0 * a), s.clone()),
-            intersection::Intersection::new((-b + f64::sqrt(discriminant)) / (2.0 * a), s.clone()),
-        ])
-    }
+pub type Intersections<'a> = BinaryHeap<Intersection<'a>>;
+pub fn intersect<'a>(r: &ray::Ray, s: &'a dyn Shape) -> Intersections<'a> {
+    let ray = r.transform(s.inverse());
+    s.local_intersect(&ray)
+}
+
+pub fn sorted<'a>(xs: Intersections<'a>) -> Vec<Intersection<'a>> {
+    let mut v = xs.into_sorted_vec();
+    v.reverse();
+    v
 }
 
-pub fn hit(i: &Intersections) -> Option<&Intersection> {
+pub fn hit<'a, 'b>(i: &'b Intersections<'a>) -> Option<&'b Intersection<'a>> {
     BinaryHeap::from_iter(i.iter().filter(|x| x.t > 0.))
         .peek()
-        .map(|x| *x)
+        .copied()
 }
 
 #[cfg(test)]
@@ -37,29 +26,28 @@ mod tests {
     use std::collections::BinaryHeap;
 
     use crate::intersect::intersect;
-    use crate::matrix::Matrix;
-    use crate::{intersection, object::Object, ray, tuple};
+    use crate::{intersection, object::Shape, ray, sphere::Sphere, tuple};
 
-    use crate::intersect::hit;
     use crate::intersect::Intersections;
+    use crate::intersect::{hit, sorted};
     #[test]
     fn test_intersect_2solutions() {
         let r = ray::Ray::new(
             tuple::Tuple::point(0., 0., -5.),
             tuple::Tuple::vector(0., 0., 1.),
         );
-        let s = Object::sphere();
+        let s = Sphere::new();
 
         let mut intersections = intersect(&r, &s);
         assert_eq!(intersections.len(), 2);
 
         let a = intersections.pop().expect("Should have solutions");
         assert_eq!(a.t, 4.0);
-        assert_eq!(a.o, Object::sphere());
+        assert_eq!(a.o, &s as &dyn Shape);
 
         let b = intersections.pop().expect("Should have solutions");
         assert_eq!(b.t, 6.0);
-        assert_eq!(b.o, Object::sphere());
+        assert_eq!(b.o, &s as &dyn Shape);
     }
     #[test]
     fn test_intersect_1solution() {
@@ -67,18 +55,18 @@ mod tests {
             tuple::Tuple::point(0., 1., -5.),
             tuple::Tuple::vector(0., 0., 1.),
         );
-        let s = Object::sphere();
+        let s = Sphere::new();
 
         let mut intersections = intersect(&r, &s);
         assert_eq!(intersections.len(), 2);
 
         let a = intersections.pop().expect("Should have solutions");
         assert_eq!(a.t, 5.0);
-        assert_eq!(a.o, Object::sphere());
+        assert_eq!(a.o, &s as &dyn Shape);
 
         let b = intersections.pop().expect("Should have Intersection");
         assert_eq!(b.t, 5.0);
-        assert_eq!(b.o, Object::sphere());
+        assert_eq!(b.o, &s as &dyn Shape);
     }
     #[test]
     fn test_intersect_miss() {
@@ -86,7 +74,7 @@ mod tests {
             tuple::Tuple::point(0., 2., 5.),
             tuple::Tuple::vector(0., 0., 1.),
         );
-        let s = Object::sphere();
+        let s = Sphere::new();
 
         let intersections = intersect(&r, &s);
         assert_eq!(intersections.len(), 0)
@@ -98,18 +86,18 @@ mod tests {
             tuple::Tuple::point(0., 0., 0.),
             tuple::Tuple::vector(0., 0., 1.),
         );
-        let s = Object::sphere();
+        let s = Sphere::new();
 
         let mut intersections = intersect(&r, &s);
         assert_eq!(intersections.len(), 2);
 
         let a = intersections.pop().expect("Should have solutions");
         assert_eq!(a.t, -1.0);
-        assert_eq!(a.o, Object::sphere());
+        assert_eq!(a.o, &s as &dyn Shape);
 
         let b = intersections.pop().expect("Should have Intersection");
         assert_eq!(b.t, 1.0);
-        assert_eq!(b.o, Object::sphere());
+        assert_eq!(b.o, &s as &dyn Shape);
     }
     #[test]
     fn test_intersect_outside() {
@@ -117,24 +105,24 @@ mod tests {
             tuple::Tuple::point(0., 0., 5.),
             tuple::Tuple::vector(0., 0., 1.),
         );
-        let s = Object::sphere();
+        let s = Sphere::new();
 
         let mut intersections = intersect(&r, &s);
         assert_eq!(intersections.len(), 2);
 
         let a = intersections.pop().expect("Should have solutions");
         assert_eq!(a.t, -6.0);
-        assert_eq!(a.o, Object::sphere());
+        assert_eq!(a.o, &s as &dyn Shape);
 
         let b = intersections.pop().expect("Should have Intersection");
         assert_eq!(b.t, -4.0);
-        assert_eq!(b.o, Object::sphere());
+        assert_eq!(b.o, &s as &dyn Shape);
     }
     #[test]
     fn test_hit_2_positive_intersection() {
-        let o = Object::sphere();
-        let i1 = intersection::Intersection::new(1., o.clone());
-        let i2 = intersection::Intersection::new(2., o.clone());
+        let o = Sphere::new();
+        let i1 = intersection::Intersection::new(1., &o);
+        let i2 = intersection::Intersection::new(2., &o);
         let i: Intersections = BinaryHeap::from([i1, i2]);
 
         let h = hit(&i);
@@ -143,9 +131,9 @@ mod tests {
     }
     #[test]
     fn test_hit_1_positive_intersection() {
-        let o = Object::sphere();
-        let i1 = intersection::Intersection::new(-1., o.clone());
-        let i2 = intersection::Intersection::new(2., o.clone());
+        let o = Sphere::new();
+        let i1 = intersection::Intersection::new(-1., &o);
+        let i2 = intersection::Intersection::new(2., &o);
         let i: Intersections = BinaryHeap::from([i1, i2]);
 
         let h = hit(&i);
@@ -154,9 +142,9 @@ mod tests {
     }
     #[test]
     fn test_hit_no_positive_intersection() {
-        let o = Object::sphere();
-        let i1 = intersection::Intersection::new(-1., o.clone());
-        let i2 = intersection::Intersection::new(-2., o.clone());
+        let o = Sphere::new();
+        let i1 = intersection::Intersection::new(-1., &o);
+        let i2 = intersection::Intersection::new(-2., &o);
         let i: Intersections = BinaryHeap::from([i1, i2]);
 
         let h = hit(&i);
@@ -166,15 +154,27 @@ mod tests {
 
     #[test]
     fn test_hit_multiple_intersection() {
-        let o = Object::sphere();
-        let i1 = intersection::Intersection::new(5., o.clone());
-        let i2 = intersection::Intersection::new(7., o.clone());
-        let i3 = intersection::Intersection::new(-3., o.clone());
-        let i4 = intersection::Intersection::new(2., o.clone());
+        let o = Sphere::new();
+        let i1 = intersection::Intersection::new(5., &o);
+        let i2 = intersection::Intersection::new(7., &o);
+        let i3 = intersection::Intersection::new(-3., &o);
+        let i4 = intersection::Intersection::new(2., &o);
         let i: Intersections = BinaryHeap::from([i1, i2, i3, i4]);
 
         let h = hit(&i);
 
         assert_eq!(h.expect("Should have solution").t, 2.)
     }
+
+    #[test]
+    fn test_sorted_ascending() {
+        let o = Sphere::new();
+        let i: Intersections = BinaryHeap::from([
+            intersection::Intersection::new(5., &o),
+            intersection::Intersection::new(-3., &o),
+            intersection::Intersection::new(2., &o),
+        ]);
+        let ts: Vec<f64> = sorted(i).iter().map(|x| x.t).collect();
+        assert_eq!(ts, vec![-3., 2., 5.]);
+    }
 }
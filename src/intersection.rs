@@ -1,43 +1,304 @@
-use crate::object::Object;
+use crate::object::{normal_at, reflect, world_to_object, Shape};
+use crate::ray::Ray;
+use crate::tuple::Tuple;
 use std::cmp::Eq;
 use std::cmp::Ord;
 use std::cmp::Ordering;
 
-#[derive(Debug, PartialEq)]
-pub struct Intersection {
+#[derive(Debug, PartialEq, Clone)]
+pub struct Intersection<'a> {
     pub t: f64,
-    pub o: Object,
+    pub o: &'a dyn Shape,
+    pub u: f64,
+    pub v: f64,
+    pub parents: Vec<&'a dyn Shape>,
 }
-impl Intersection {
-    pub fn new(t: f64, o: Object) -> Self {
-        Intersection { t: t, o: o }
+impl<'a> Intersection<'a> {
+    pub fn new(t: f64, o: &'a dyn Shape) -> Self {
+        Intersection::with_uv(t, o, 0., 0.)
+    }
+    pub fn with_uv(t: f64, o: &'a dyn Shape, u: f64, v: f64) -> Self {
+        Intersection {
+            t,
+            o,
+            u,
+            v,
+            parents: vec![],
+        }
     }
 }
-impl Eq for Intersection {}
-impl PartialOrd for Intersection {
-    fn partial_cmp(&self, other: &Intersection) -> Option<Ordering> {
-        self.t.partial_cmp(&other.t).map(|x| x.reverse())
+impl Eq for Intersection<'_> {}
+impl PartialOrd for Intersection<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
-impl Ord for Intersection {
+impl Ord for Intersection<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
         self.t.total_cmp(&other.t).reverse()
     }
 }
+
+pub const EPSILON: f64 = 0.00001;
+
+pub struct Computations<'a> {
+    pub t: f64,
+    pub o: &'a dyn Shape,
+    pub point: Tuple,
+    pub object_point: Tuple,
+    pub over_point: Tuple,
+    pub under_point: Tuple,
+    pub eyev: Tuple,
+    pub normalv: Tuple,
+    pub reflectv: Tuple,
+    pub inside: bool,
+    pub n1: f64,
+    pub n2: f64,
+}
+
+fn refractive_indices(hit: &Intersection, xs: &[Intersection]) -> (f64, f64) {
+    let mut containers: Vec<&dyn Shape> = vec![];
+    let index_of = |containers: &[&dyn Shape]| {
+        containers
+            .last()
+            .map_or(1., |o| o.material().refractive_index)
+    };
+    for i in xs {
+        let n1 = index_of(&containers);
+        match containers.iter().position(|&o| o == i.o) {
+            Some(pos) => {
+                containers.remove(pos);
+            }
+            None => containers.push(i.o),
+        }
+        if i == hit {
+            return (n1, index_of(&containers));
+        }
+    }
+    (1., 1.)
+}
+
+pub fn prepare_computations<'a>(
+    i: &Intersection<'a>,
+    r: &Ray,
+    xs: &[Intersection<'a>],
+) -> Computations<'a> {
+    let point = r.position_at(i.t);
+    let eyev = -r.direction;
+    let normalv = normal_at(i.o, point, i);
+    let inside = normalv.dot(&eyev) < 0.;
+    let normalv = if inside { -normalv } else { normalv };
+    let (n1, n2) = refractive_indices(i, xs);
+    Computations {
+        t: i.t,
+        o: i.o,
+        point,
+        object_point: world_to_object(i.o, &i.parents, point),
+        over_point: point + normalv * EPSILON,
+        under_point: point - normalv * EPSILON,
+        eyev,
+        normalv,
+        reflectv: reflect(&r.direction, &normalv),
+        inside,
+        n1,
+        n2,
+    }
+}
+
+pub fn schlick(comps: &Computations) -> f64 {
+    let mut cos = comps.eyev.dot(&comps.normalv);
+    if comps.n1 > comps.n2 {
+        let n = comps.n1 / comps.n2;
+        let sin2_t = n * n * (1. - cos * cos);
+        if sin2_t > 1. {
+            return 1.;
+        }
+        cos = (1. - sin2_t).sqrt();
+    }
+    let r0 = ((comps.n1 - comps.n2) / (comps.n1 + comps.n2)).powi(2);
+    r0 + (1. - r0) * (1. - cos).powi(5)
+}
 #[cfg(test)]
 mod tests {
 
-    use crate::{intersection::Intersection, object::Object};
+    use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+    use crate::{
+        intersection::{prepare_computations, schlick, Intersection, EPSILON},
+        material::Material,
+        matrix::Matrix,
+        object::Shape,
+        plane::Plane,
+        ray::Ray,
+        sphere::Sphere,
+        tuple::Tuple,
+    };
     #[test]
     fn test_intersection() {
-        let sphere = Object::sphere();
-        let a = Intersection {
-            t: 1.,
-            o: sphere.clone(),
-        };
+        let sphere = Sphere::new();
+        let a = Intersection::new(1., &sphere);
 
         assert_eq!(a.t, 1.);
 
-        assert_eq!(a.o, sphere);
+        assert_eq!(a.o, &sphere as &dyn Shape);
+    }
+
+    #[test]
+    fn test_prepare_computations() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let i = Intersection::new(4., &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_eq!(comps.t, i.t);
+        assert_eq!(comps.o, &shape as &dyn Shape);
+        assert_eq!(comps.point, Tuple::point(0., 0., -1.));
+        assert_eq!(comps.eyev, Tuple::vector(0., 0., -1.));
+        assert_eq!(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_prepare_computations_outside() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let i = Intersection::new(4., &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn test_prepare_computations_inside() {
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let i = Intersection::new(1., &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_eq!(comps.point, Tuple::point(0., 0., 1.));
+        assert_eq!(comps.eyev, Tuple::vector(0., 0., -1.));
+        assert!(comps.inside);
+        assert_eq!(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_prepare_computations_over_point() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut shape = Sphere::new();
+        shape
+            .set_transform(Matrix::translation(0., 0., 1.))
+            .expect("Transform should be invertible");
+        let i = Intersection::new(5., &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert!(comps.over_point.z < -EPSILON / 2.);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn test_prepare_computations_reflectv() {
+        let shape = Plane::new();
+        let r = Ray::new(
+            Tuple::point(0., 1., -1.),
+            Tuple::vector(0., -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = Intersection::new(SQRT_2, &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+        assert_eq!(
+            comps.reflectv,
+            Tuple::vector(0., FRAC_1_SQRT_2, FRAC_1_SQRT_2)
+        );
+    }
+
+    fn glass_sphere() -> Sphere {
+        let mut mat = Material::default();
+        mat.transparency = 1.;
+        mat.refractive_index = 1.5;
+        Sphere::with_mat(mat)
+    }
+
+    #[test]
+    fn test_n1_n2_at_various_intersections() {
+        let mut a = glass_sphere();
+        a.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        let mut b = glass_sphere();
+        b.set_transform(Matrix::translation(0., 0., -0.25))
+            .expect("Transform should be invertible");
+        let mut mat = b.material().clone();
+        mat.refractive_index = 2.;
+        b.set_material(mat);
+        let mut c = glass_sphere();
+        c.set_transform(Matrix::translation(0., 0., 0.25))
+            .expect("Transform should be invertible");
+        let mut mat = c.material().clone();
+        mat.refractive_index = 2.5;
+        c.set_material(mat);
+
+        let r = Ray::new(Tuple::point(0., 0., -4.), Tuple::vector(0., 0., 1.));
+        let xs = vec![
+            Intersection::new(2., &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6., &a),
+        ];
+        let expected = [
+            (1., 1.5),
+            (1.5, 2.),
+            (2., 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.),
+        ];
+        for (i, (n1, n2)) in xs.iter().zip(expected) {
+            let comps = prepare_computations(i, &r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2));
+        }
+    }
+
+    #[test]
+    fn test_prepare_computations_under_point() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut shape = glass_sphere();
+        shape
+            .set_transform(Matrix::translation(0., 0., 1.))
+            .expect("Transform should be invertible");
+        let i = Intersection::new(5., &shape);
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+        assert!(comps.under_point.z > EPSILON / 2.);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+
+    #[test]
+    fn test_schlick_total_internal_reflection() {
+        let shape = glass_sphere();
+        let r = Ray::new(
+            Tuple::point(0., 0., FRAC_1_SQRT_2),
+            Tuple::vector(0., 1., 0.),
+        );
+        let xs = vec![
+            Intersection::new(-FRAC_1_SQRT_2, &shape),
+            Intersection::new(FRAC_1_SQRT_2, &shape),
+        ];
+        let comps = prepare_computations(&xs[1], &r, &xs);
+        assert_eq!(schlick(&comps), 1.);
+    }
+
+    #[test]
+    fn test_schlick_perpendicular_and_grazing() {
+        let shape = glass_sphere();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        let xs = vec![
+            Intersection::new(-1., &shape),
+            Intersection::new(1., &shape),
+        ];
+        let comps = prepare_computations(&xs[1], &r, &xs);
+        assert!((schlick(&comps) - 0.04).abs() < 0.0001);
+
+        let r = Ray::new(Tuple::point(0., 0.99, -2.), Tuple::vector(0., 0., 1.));
+        let xs = vec![Intersection::new(1.8589, &shape)];
+        let comps = prepare_computations(&xs[0], &r, &xs);
+        assert!((schlick(&comps) - 0.48873).abs() < 0.0001);
     }
 }
@@ -0,0 +1,29 @@
+pub mod bounds;
+pub mod camera;
+pub mod canvas;
+pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
+pub mod hdr;
+pub mod image_file;
+pub mod intersect;
+pub mod intersection;
+pub mod material;
+pub mod matrix;
+pub mod obj_file;
+pub mod object;
+pub mod output_transform;
+pub mod pattern;
+pub mod perlin;
+pub mod plane;
+pub mod png;
+pub mod point_light;
+pub mod ray;
+pub mod scene_file;
+pub mod sphere;
+pub mod triangle;
+pub mod tuple;
+pub mod world;
@@ -1,62 +1,440 @@
-mod canvas;
-mod color;
-mod intersect;
-mod intersection;
-mod material;
-mod matrix;
-mod object;
-mod point_light;
-mod ray;
-mod tuple;
-use canvas::Canvas;
-use color::Color;
-use intersect::{hit, intersect};
-use material::Material;
-use object::{normal_at, Object};
-use point_light::Point_Light;
-use tuple::Tuple;
-
-use crate::matrix::Matrix;
-use crate::ray::Ray;
-fn main() {
-    let canvas_pixels = 1024;
-    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
-    let ray_origin = Tuple::point(0., 0., -5.);
-
-    let wall_z = 10.0f64;
-    let wall_size = 7.0f64;
-    let pixel_size = wall_size / (canvas_pixels as f64);
-    let half = wall_size / 2.;
-    let mut mat = Material::default();
-    mat.color = Color::new(1., 0.2, 1.);
-    let shape = Object::sphere_with_mat(mat);
-
-    let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(-10., 10., -10.));
-    for y in 0..canvas_pixels {
-        let world_y: f64 = half - pixel_size * (y as f64);
-        for x in 0..canvas_pixels {
-            let world_x: f64 = half - pixel_size * (x as f64);
-            let position = Tuple::point(world_x, world_y, wall_z);
-
-            let r = Ray::new(ray_origin, (position - ray_origin).normalize());
-            let xs = intersect(&r, &shape);
-            match hit(&xs) {
-                Some(hit_point) => {
-                    let point = r.position_at(hit_point.t);
-                    let normal = normal_at(&hit_point.o, point);
-                    let eye = -r.direction;
-                    let color = match &hit_point.o {
-                        Object::Sphere(_, mat) => {
-                            material::lighting(&mat, &light, &point, &eye, &normal)
-                        }
-                    };
-
-                    canvas.set_pix((x, y), color)
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rtchallenge::camera::{Camera, RenderOptions};
+use rtchallenge::canvas::Canvas;
+use rtchallenge::output_transform::{Encoding, OutputTransform, ToneMap};
+use rtchallenge::png::{BitDepth, Compression};
+use rtchallenge::scene_file::parse_scene_file;
+
+const USAGE: &str = "usage: rtchallenge SCENE [options]
+
+Renders a YAML scene file. The output format follows the file extension:
+.ppm, .png, .pfm or .hdr.
+
+options:
+  -o, --output PATH    output image (default: out.png)
+      --width N        override the camera width in pixels
+      --height N       override the camera height in pixels
+  -s, --samples N      samples per pixel (default: 1)
+  -d, --max-depth N    reflection and refraction depth (default: from the scene, 5)
+  -t, --threads N      render threads (default: one per core)
+      --exposure X     scale colors by X before tone mapping (default: 1)
+      --tone-map NAME  none, reinhard or aces (default: none)
+      --srgb           encode colors with the sRGB curve instead of linearly
+  -q, --quiet          do not report progress
+      --help           show this message
+
+exit codes: 0 success, 2 invalid arguments, 3 unreadable or invalid scene,
+4 output could not be written";
+
+const DIVIDE_THRESHOLD: usize = 4;
+// 16384x16384, a little over 6GB of canvas.
+const MAX_PIXELS: usize = 1 << 28;
+
+#[derive(Debug, PartialEq)]
+enum Failure {
+    Usage(String),
+    Scene(String),
+    Output(String),
+}
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Usage(_) => 2,
+            Failure::Scene(_) => 3,
+            Failure::Output(_) => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ppm,
+    Png,
+    Pfm,
+    Hdr,
+}
+impl Format {
+    fn from_path(path: &Path) -> Result<Self, Failure> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("ppm") => Ok(Format::Ppm),
+            Some("png") => Ok(Format::Png),
+            Some("pfm") => Ok(Format::Pfm),
+            Some("hdr") => Ok(Format::Hdr),
+            _ => Err(Failure::Usage(format!(
+                "cannot tell the image format of '{}', use .ppm, .png, .pfm or .hdr",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    scene: PathBuf,
+    output: PathBuf,
+    format: Format,
+    width: Option<usize>,
+    height: Option<usize>,
+    max_depth: Option<usize>,
+    render: RenderOptions,
+    output_transform: OutputTransform,
+    quiet: bool,
+}
+
+fn parse_count(option: &str, value: Option<String>, allow_zero: bool) -> Result<usize, Failure> {
+    let Some(value) = value else {
+        return Err(Failure::Usage(format!("{option} needs a value")));
+    };
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 || allow_zero => Ok(n),
+        _ => Err(Failure::Usage(format!(
+            "invalid value '{value}' for {option}"
+        ))),
+    }
+}
+
+fn parse_exposure(option: &str, value: Option<String>) -> Result<f64, Failure> {
+    let Some(value) = value else {
+        return Err(Failure::Usage(format!("{option} needs a value")));
+    };
+    match value.parse::<f64>() {
+        Ok(x) if x > 0. && x.is_finite() => Ok(x),
+        _ => Err(Failure::Usage(format!(
+            "invalid value '{value}' for {option}"
+        ))),
+    }
+}
+
+fn parse_tone_map(option: &str, value: Option<String>) -> Result<ToneMap, Failure> {
+    match value.as_deref() {
+        Some("none") => Ok(ToneMap::None),
+        Some("reinhard") => Ok(ToneMap::Reinhard),
+        Some("aces") => Ok(ToneMap::Aces),
+        Some(other) => Err(Failure::Usage(format!(
+            "invalid value '{other}' for {option}, use none, reinhard or aces"
+        ))),
+        None => Err(Failure::Usage(format!("{option} needs a value"))),
+    }
+}
+
+// Returns None when the user asked for help.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, Failure> {
+    let mut scene = None;
+    let mut output = PathBuf::from("out.png");
+    let mut width = None;
+    let mut height = None;
+    let mut max_depth = None;
+    let mut samples = 1;
+    let mut threads = None;
+    let mut output_transform = OutputTransform::default();
+    let mut quiet = false;
+
+    while let Some(arg) = args.next() {
+        let (option, mut inline) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => {
+                (option.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || inline.take().or_else(|| args.next());
+        match option.as_str() {
+            "--help" => return Ok(None),
+            "-o" | "--output" => match value() {
+                Some(path) => output = PathBuf::from(path),
+                None => return Err(Failure::Usage(format!("{option} needs a value"))),
+            },
+            "--width" => width = Some(parse_count(&option, value(), false)?),
+            "--height" => height = Some(parse_count(&option, value(), false)?),
+            "-s" | "--samples" => samples = parse_count(&option, value(), false)?,
+            "-d" | "--max-depth" => max_depth = Some(parse_count(&option, value(), true)?),
+            "-t" | "--threads" => threads = Some(parse_count(&option, value(), false)?),
+            "--exposure" => output_transform.exposure = parse_exposure(&option, value())?,
+            "--tone-map" => output_transform.tone_map = parse_tone_map(&option, value())?,
+            "--srgb" => output_transform.encoding = Encoding::Srgb,
+            "-q" | "--quiet" => quiet = true,
+            _ if option.starts_with('-') && option != "-" => {
+                return Err(Failure::Usage(format!("unknown option '{option}'")))
+            }
+            _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument '{arg}'"))),
+        }
+        if inline.is_some() {
+            return Err(Failure::Usage(format!("{option} does not take a value")));
+        }
+    }
+
+    let Some(scene) = scene else {
+        return Err(Failure::Usage("missing scene file".to_string()));
+    };
+    let threads = threads.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    Ok(Some(Options {
+        scene,
+        format: Format::from_path(&output)?,
+        output,
+        width,
+        height,
+        max_depth,
+        render: RenderOptions {
+            samples,
+            threads,
+            ..RenderOptions::default()
+        },
+        output_transform,
+        quiet,
+    }))
+}
+
+fn write_image(canvas: &Canvas, path: &Path, format: Format) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Ppm => canvas.to_ppm_binary(&mut file),
+        Format::Png => canvas.to_png(&mut file, BitDepth::Eight, Compression::Fixed),
+        Format::Pfm => canvas.to_pfm(&mut file),
+        Format::Hdr => canvas.to_hdr(&mut file),
+    }?;
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+// Workers finish out of order, so only report a percentage above the last one.
+fn advance_progress(shown: &AtomicUsize, done: usize, total: usize) -> Option<usize> {
+    let percent = done * 100 / total;
+    (percent > shown.fetch_max(percent, Ordering::Relaxed)).then_some(percent)
+}
+
+fn check_image_size(width: usize, height: usize) -> Result<(), String> {
+    match width.checked_mul(height) {
+        Some(0) => Err(format!("image size {width}x{height} has no pixels")),
+        Some(pixels) if pixels <= MAX_PIXELS => Ok(()),
+        _ => Err(format!(
+            "image size {width}x{height} is larger than {MAX_PIXELS} pixels"
+        )),
+    }
+}
+
+fn run(options: &Options) -> Result<(), Failure> {
+    let input = std::fs::read_to_string(&options.scene)
+        .map_err(|e| Failure::Scene(format!("cannot read '{}': {e}", options.scene.display())))?;
+    let base_dir = options.scene.parent().unwrap_or(Path::new("."));
+    let mut scene = parse_scene_file(&input, base_dir)
+        .map_err(|e| Failure::Scene(format!("{}: {e}", options.scene.display())))?;
+
+    let mut camera = scene.camera;
+    let resize = options.width.is_some() || options.height.is_some();
+    let width = options.width.unwrap_or(camera.hsize);
+    let height = options.height.unwrap_or(camera.vsize);
+    check_image_size(width, height).map_err(|message| {
+        if resize {
+            Failure::Usage(message)
+        } else {
+            Failure::Scene(format!("{}: {message}", options.scene.display()))
+        }
+    })?;
+    if resize {
+        let mut resized = Camera::new(width, height, camera.field_of_view);
+        resized
+            .set_transform(*camera.transform())
+            .map_err(|e| Failure::Scene(format!("{}: camera {e}", options.scene.display())))?;
+        camera = resized;
+    }
+    if let Some(depth) = options.max_depth {
+        scene.world.max_depth = depth;
+    }
+    for object in scene.world.objects.iter_mut() {
+        object.divide(DIVIDE_THRESHOLD);
+    }
+
+    let shown_percent = AtomicUsize::new(0);
+    let mut canvas = camera.render_with(&scene.world, options.render, |done, total| {
+        if options.quiet {
+            return;
+        }
+        // Holding the lock while checking keeps a worker that reports late
+        // from printing an older percentage over a newer one.
+        let mut stderr = io::stderr().lock();
+        if let Some(percent) = advance_progress(&shown_percent, done, total) {
+            let _ = write!(
+                stderr,
+                "\rrendering {}x{}: {percent:3}%",
+                camera.hsize, camera.vsize
+            );
+            let _ = stderr.flush();
+        }
+    });
+    if !options.quiet {
+        let _ = writeln!(io::stderr());
+    }
+
+    canvas.set_output_transform(options.output_transform);
+    write_image(&canvas, &options.output, options.format)
+        .map_err(|e| Failure::Output(format!("cannot write '{}': {e}", options.output.display())))
+}
+
+fn main() -> ExitCode {
+    let result = parse_args(std::env::args().skip(1)).and_then(|options| match options {
+        Some(options) => run(&options),
+        None => {
+            // Ignore failures such as a closed pipe rather than panicking.
+            let _ = writeln!(io::stdout(), "{USAGE}");
+            Ok(())
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            let _ = match &failure {
+                Failure::Usage(message) => writeln!(io::stderr(), "error: {message}\n\n{USAGE}"),
+                Failure::Scene(message) | Failure::Output(message) => {
+                    writeln!(io::stderr(), "error: {message}")
                 }
-                _ => (),
+            };
+            ExitCode::from(failure.exit_code())
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicUsize;
+
+    use rtchallenge::camera::RenderOptions;
+    use rtchallenge::output_transform::{Encoding, OutputTransform, ToneMap};
+
+    use super::{
+        advance_progress, check_image_size, parse_args, Failure, Format, Options, MAX_PIXELS,
+    };
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, Failure> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse(&[
+            "scene.yml",
+            "-o",
+            "render.HDR",
+            "--width=320",
+            "--height",
+            "200",
+            "-s",
+            "16",
+            "--max-depth",
+            "0",
+            "-t",
+            "3",
+            "--exposure",
+            "1.5",
+            "--tone-map=aces",
+            "--srgb",
+            "--quiet",
+        ])
+        .expect("Should parse")
+        .expect("Should not ask for help");
+        assert_eq!(
+            options,
+            Options {
+                scene: PathBuf::from("scene.yml"),
+                output: PathBuf::from("render.HDR"),
+                format: Format::Hdr,
+                width: Some(320),
+                height: Some(200),
+                max_depth: Some(0),
+                render: RenderOptions {
+                    samples: 16,
+                    threads: 3,
+                    ..RenderOptions::default()
+                },
+                output_transform: OutputTransform::new(1.5, ToneMap::Aces, Encoding::Srgb),
+                quiet: true,
             }
+        );
+    }
+
+    #[test]
+    fn test_parse_args_defaults_and_help() {
+        let options = parse(&["scene.yml"])
+            .expect("Should parse")
+            .expect("Should not ask for help");
+        assert_eq!(options.output, PathBuf::from("out.png"));
+        assert_eq!(options.format, Format::Png);
+        assert_eq!(options.render.samples, 1);
+        assert!(options.render.threads >= 1);
+        assert_eq!(options.output_transform, OutputTransform::default());
+        assert!(!options.quiet);
+
+        assert_eq!(parse(&["--help"]), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let cases: [(&[&str], &str); 9] = [
+            (&[], "missing scene file"),
+            (&["a.yml", "b.yml"], "unexpected argument 'b.yml'"),
+            (&["a.yml", "--fast"], "unknown option '--fast'"),
+            (
+                &["a.yml", "--samples", "0"],
+                "invalid value '0' for --samples",
+            ),
+            (&["a.yml", "--width"], "--width needs a value"),
+            (&["a.yml", "--quiet=yes"], "--quiet does not take a value"),
+            (
+                &["a.yml", "--exposure", "-1"],
+                "invalid value '-1' for --exposure",
+            ),
+            (
+                &["a.yml", "--tone-map=filmic"],
+                "invalid value 'filmic' for --tone-map, use none, reinhard or aces",
+            ),
+            (
+                &["a.yml", "-o", "a.jpg"],
+                "cannot tell the image format of 'a.jpg', use .ppm, .png, .pfm or .hdr",
+            ),
+        ];
+        for (args, message) in cases {
+            let err = parse(args).expect_err("Should fail");
+            assert_eq!(err, Failure::Usage(message.to_string()));
+            assert_eq!(err.exit_code(), 2);
         }
     }
-    let mut file = std::fs::File::create("foo.ppm").expect("msg");
-    canvas.to_ppm(&mut file);
+
+    #[test]
+    fn test_progress_never_goes_backwards() {
+        let shown = AtomicUsize::new(0);
+        assert_eq!(advance_progress(&shown, 0, 100), None);
+        assert_eq!(advance_progress(&shown, 57, 100), Some(57));
+        assert_eq!(advance_progress(&shown, 56, 100), None);
+        assert_eq!(advance_progress(&shown, 57, 100), None);
+        assert_eq!(advance_progress(&shown, 3, 4), Some(75));
+        assert_eq!(advance_progress(&shown, 4, 4), Some(100));
+    }
+
+    #[test]
+    fn test_check_image_size() {
+        assert_eq!(check_image_size(1920, 1080), Ok(()));
+        assert_eq!(check_image_size(1, MAX_PIXELS), Ok(()));
+        assert_eq!(
+            check_image_size(0, 10),
+            Err("image size 0x10 has no pixels".to_string())
+        );
+        assert_eq!(
+            check_image_size(100000000, 100000000),
+            Err(format!(
+                "image size 100000000x100000000 is larger than {MAX_PIXELS} pixels"
+            ))
+        );
+        assert!(check_image_size(usize::MAX, 2).is_err());
+    }
 }
@@ -1,43 +1,93 @@
-use crate::{color::Color, object::reflect, point_light::Point_Light, tuple::Tuple};
-#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    object::reflect,
+    pattern::{pattern_at, Pattern},
+    point_light::Point_Light,
+    tuple::Tuple,
+};
+#[derive(Debug, Clone)]
 pub struct Material {
     pub color: Color,
+    pub pattern: Option<Arc<dyn Pattern>>,
     pub ambiant: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
 }
 impl Material {
     pub fn new(color: Color, ambiant: f64, diffuse: f64, specular: f64, shininess: f64) -> Self {
         Material {
             color,
+            pattern: None,
             ambiant,
             diffuse,
             specular,
             shininess,
+            reflective: 0.,
+            transparency: 0.,
+            refractive_index: 1.,
         }
     }
     pub fn default() -> Self {
         Material {
             color: Color::new(1., 1., 1.),
+            pattern: None,
             ambiant: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.,
+            transparency: 0.,
+            refractive_index: 1.,
+        }
+    }
+
+    pub fn color_at(&self, object_point: &Tuple) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern_at(pattern.as_ref(), *object_point),
+            None => self.color,
         }
     }
 }
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        let same_pattern = match (&self.pattern, &other.pattern) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_pattern
+            && self.color == other.color
+            && self.ambiant == other.ambiant
+            && self.diffuse == other.diffuse
+            && self.specular == other.specular
+            && self.shininess == other.shininess
+            && self.reflective == other.reflective
+            && self.transparency == other.transparency
+            && self.refractive_index == other.refractive_index
+    }
+}
 
 pub fn lighting(
     material: &Material,
     light: &Point_Light,
     point: &Tuple,
+    object_point: &Tuple,
     eyev: &Tuple,
     normalv: &Tuple,
+    in_shadow: bool,
 ) -> Color {
-    let effective_color = material.color * light.intensity;
+    let effective_color = material.color_at(object_point) * light.intensity;
     let lightv = (light.position - *point).normalize();
     let ambient = effective_color * material.ambiant;
+    if in_shadow {
+        return ambient;
+    }
     let light_dot_normal = lightv.dot(&normalv);
     let mut diffuse = Color::new(0., 0., 0.);
     let mut specular = Color::new(0., 0., 0.);
@@ -55,8 +105,11 @@ pub fn lighting(
 #[cfg(test)]
 mod tests {
 
+    use std::sync::Arc;
+
     use crate::{
         color::Color,
+        pattern::{Pattern, Stripe},
         point_light::{self, Point_Light},
         tuple::Tuple,
     };
@@ -71,6 +124,9 @@ mod tests {
         assert_eq!(m.ambiant, 0.1);
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.);
+        assert_eq!(m.transparency, 0.);
+        assert_eq!(m.refractive_index, 1.);
     }
 
     #[test]
@@ -82,7 +138,7 @@ mod tests {
         let normalv = Tuple::vector(0., 0., -1.);
         let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 0., -10.));
 
-        let result = lighting(&m, &light, &position, &eyev, &normalv);
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
     #[test]
@@ -94,7 +150,7 @@ mod tests {
         let normalv = Tuple::vector(0., 0., -1.);
         let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 0., -10.));
 
-        let result = lighting(&m, &light, &position, &eyev, &normalv);
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
 
@@ -107,7 +163,7 @@ mod tests {
         let normalv = Tuple::vector(0., 0., -1.);
         let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 10., -10.));
 
-        let result = lighting(&m, &light, &position, &eyev, &normalv);
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, false);
         assert!(result.r - 0.7364 < 0.0001);
         assert!(result.g - 0.7364 < 0.0001);
         assert!(result.b - 0.7364 < 0.0001);
@@ -121,7 +177,7 @@ mod tests {
         let normalv = Tuple::vector(0., 0., -1.);
         let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 10., -10.));
 
-        let result = lighting(&m, &light, &position, &eyev, &normalv);
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, false);
         assert!(result.r - 1.6364 < 0.0001);
         assert!(result.g - 1.6364 < 0.0001);
         assert!(result.b - 1.6364 < 0.0001);
@@ -135,9 +191,55 @@ mod tests {
         let normalv = Tuple::vector(0., 0., -1.);
         let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 0., 10.));
 
-        let result = lighting(&m, &light, &position, &eyev, &normalv);
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, false);
         assert!(result.r - 0.1 < 0.0001);
         assert!(result.g - 0.1 < 0.0001);
         assert!(result.b - 0.1 < 0.0001);
     }
+    #[test]
+    fn test_light_shading_in_shadow() {
+        let m = Material::default();
+        let position = Tuple::point(0., 0., 0.);
+
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 0., -10.));
+
+        let result = lighting(&m, &light, &position, &position, &eyev, &normalv, true);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn test_lighting_with_pattern() {
+        let mut m = Material::default();
+        m.pattern = Some(Arc::new(Stripe::new(
+            Color::new(1., 1., 1.),
+            Color::new(0., 0., 0.),
+        )));
+        m.ambiant = 1.;
+        m.diffuse = 0.;
+        m.specular = 0.;
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(0., 0., -10.));
+
+        let p1 = Tuple::point(0.9, 0., 0.);
+        let p2 = Tuple::point(1.1, 0., 0.);
+        let c1 = lighting(&m, &light, &p1, &p1, &eyev, &normalv, false);
+        let c2 = lighting(&m, &light, &p2, &p2, &eyev, &normalv, false);
+        assert_eq!(c1, Color::new(1., 1., 1.));
+        assert_eq!(c2, Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_material_eq_compares_pattern() {
+        let pattern: Arc<dyn Pattern> =
+            Arc::new(Stripe::new(Color::new(1., 1., 1.), Color::new(0., 0., 0.)));
+        let mut m1 = Material::default();
+        m1.pattern = Some(pattern.clone());
+        let mut m2 = Material::default();
+        assert_ne!(m1, m2);
+        m2.pattern = Some(pattern);
+        assert_eq!(m1, m2);
+    }
 }
@@ -1,155 +1,136 @@
 use crate::tuple;
+use std::fmt;
 use std::ops;
 use std::ops::{Index, IndexMut};
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Matrix {
-    shape: (usize, usize),
-    m: Vec<Vec<f64>>,
+    m: [[f64; 4]; 4],
 }
 impl Matrix {
-    pub fn new(width: usize, height: usize) -> Self {
-        Matrix {
-            shape: (width, height),
-            m: vec![vec![0.0f64; width]; height],
-        }
-    }
-    pub fn from(width: usize, height: usize, data: Vec<Vec<f64>>) -> Self {
-        Matrix {
-            shape: (width, height),
-            m: data,
-        }
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Matrix { m }
     }
     pub fn identity() -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1.0, 0., 0., 0.],
-                vec![0.0, 1., 0., 0.],
-                vec![0.0, 0., 1., 0.],
-                vec![0.0, 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn translation(x: f64, y: f64, z: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1.0, 0., 0., x],
-                vec![0.0, 1., 0., y],
-                vec![0.0, 0., 1., z],
-                vec![0.0, 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [1., 0., 0., x],
+            [0., 1., 0., y],
+            [0., 0., 1., z],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn scale(x: f64, y: f64, z: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![x, 0., 0., 0.],
-                vec![0.0, y, 0., 0.],
-                vec![0.0, 0., z, 0.],
-                vec![0.0, 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [x, 0., 0., 0.],
+            [0., y, 0., 0.],
+            [0., 0., z, 0.],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn rot_x(a: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 0., 0., 0.],
-                vec![0.0, f64::cos(a), -f64::sin(a), 0.],
-                vec![0.0, f64::sin(a), f64::cos(a), 0.],
-                vec![0.0, 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [1., 0., 0., 0.],
+            [0., f64::cos(a), -f64::sin(a), 0.],
+            [0., f64::sin(a), f64::cos(a), 0.],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn rot_y(a: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![f64::cos(a), 0., f64::sin(a), 0.],
-                vec![0.0, 1., 0., 0.],
-                vec![-f64::sin(a), 0., f64::cos(a), 0.],
-                vec![0.0, 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [f64::cos(a), 0., f64::sin(a), 0.],
+            [0., 1., 0., 0.],
+            [-f64::sin(a), 0., f64::cos(a), 0.],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn rot_z(a: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![f64::cos(a), -f64::sin(a), 0., 0.],
-                vec![f64::sin(a), f64::cos(a), 0., 0.],
-                vec![0., 0., 1., 0.],
-                vec![0., 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [f64::cos(a), -f64::sin(a), 0., 0.],
+            [f64::sin(a), f64::cos(a), 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
     }
     pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
-        Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., xy, xz, 0.],
-                vec![yx, 1., yz, 0.],
-                vec![zx, zy, 1., 0.],
-                vec![0., 0., 0., 1.],
-            ],
-        )
+        Matrix::new([
+            [1., xy, xz, 0.],
+            [yx, 1., yz, 0.],
+            [zx, zy, 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+    pub fn view_transform(from: tuple::Tuple, to: tuple::Tuple, up: tuple::Tuple) -> Self {
+        let forward = (to - from).normalize();
+        let left = forward.cross(&up.normalize());
+        let true_up = left.cross(&forward);
+        let orientation = Matrix::new([
+            [left.x, left.y, left.z, 0.],
+            [true_up.x, true_up.y, true_up.z, 0.],
+            [-forward.x, -forward.y, -forward.z, 0.],
+            [0., 0., 0., 1.],
+        ]);
+        orientation * Matrix::translation(-from.x, -from.y, -from.z)
     }
     pub fn transpose(&self) -> Self {
-        let mut m = Matrix::new(4, 4);
-        for y in 0..4 as usize {
-            for x in 0..4 as usize {
+        let mut m = Matrix::new([[0.; 4]; 4]);
+        for y in 0..4 {
+            for x in 0..4 {
                 m[(x, y)] = self[(y, x)];
             }
         }
         m
     }
 
+    // 2x2 determinants of the top two rows (s) and bottom two rows (c), from
+    // which both the determinant and the inverse follow by Laplace expansion.
+    fn sub_factors(&self) -> ([f64; 6], [f64; 6]) {
+        let a = &self.m;
+        let s = [
+            a[0][0] * a[1][1] - a[1][0] * a[0][1],
+            a[0][0] * a[1][2] - a[1][0] * a[0][2],
+            a[0][0] * a[1][3] - a[1][0] * a[0][3],
+            a[0][1] * a[1][2] - a[1][1] * a[0][2],
+            a[0][1] * a[1][3] - a[1][1] * a[0][3],
+            a[0][2] * a[1][3] - a[1][2] * a[0][3],
+        ];
+        let c = [
+            a[2][0] * a[3][1] - a[3][0] * a[2][1],
+            a[2][0] * a[3][2] - a[3][0] * a[2][2],
+            a[2][0] * a[3][3] - a[3][0] * a[2][3],
+            a[2][1] * a[3][2] - a[3][1] * a[2][2],
+            a[2][1] * a[3][3] - a[3][1] * a[2][3],
+            a[2][2] * a[3][3] - a[3][2] * a[2][3],
+        ];
+        (s, c)
+    }
+
     pub fn determinant(&self) -> f64 {
-        if self.shape == (2, 2) {
-            self[(0, 0)] * self[(1, 1)] - self[(1, 0)] * self[(0, 1)]
-        } else {
-            (0..self.shape.0)
-                .map(|x| self[(0, x)] * self.cofactor(0, x))
-                .sum()
-        }
+        let (s, c) = self.sub_factors();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
     }
 
-    pub fn submatrix(&self, r: usize, c: usize) -> Self {
-        Matrix::from(
-            self.shape.0 - 1,
-            self.shape.1 - 1,
-            self.m
-                .iter()
-                .enumerate()
-                .filter_map(|(pos, e)| {
-                    if pos == r {
-                        None
-                    } else {
-                        Some(
-                            e.iter()
-                                .enumerate()
-                                .filter_map(
-                                    |(cpos, cell)| if cpos == c { None } else { Some(*cell) },
-                                )
-                                .collect(),
-                        )
-                    }
-                })
-                .collect(),
-        )
+    pub fn submatrix(&self, r: usize, c: usize) -> [[f64; 3]; 3] {
+        let mut sub = [[0.; 3]; 3];
+        for (sr, row) in (0..4).filter(|&row| row != r).enumerate() {
+            for (sc, col) in (0..4).filter(|&col| col != c).enumerate() {
+                sub[sr][sc] = self.m[row][col];
+            }
+        }
+        sub
     }
     pub fn minor(&self, r: usize, c: usize) -> f64 {
-        self.submatrix(r, c).determinant()
+        let m = self.submatrix(r, c);
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
     }
 
     pub fn cofactor(&self, r: usize, c: usize) -> f64 {
@@ -160,35 +141,95 @@ impl Matrix {
         }
     }
     pub fn is_inversible(&self) -> bool {
-        self.determinant() != 0f64
+        let det = self.determinant();
+        det != 0f64 && det.is_finite()
     }
 
     pub fn inverse(&self) -> Option<Self> {
-        if !self.is_inversible() {
-            None
-        } else {
-            let mut m = Matrix::new(self.shape.0, self.shape.1);
-            let det = self.determinant();
-            for row in 0..self.shape.0 {
-                for col in 0..self.shape.1 {
-                    let c = self.cofactor(row, col);
-                    m[(col, row)] = c / det;
-                }
-            }
-            Some(m)
+        let a = &self.m;
+        let (s, c) = self.sub_factors();
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let adjugate = [
+            [
+                a[1][1] * c[5] - a[1][2] * c[4] + a[1][3] * c[3],
+                -a[0][1] * c[5] + a[0][2] * c[4] - a[0][3] * c[3],
+                a[3][1] * s[5] - a[3][2] * s[4] + a[3][3] * s[3],
+                -a[2][1] * s[5] + a[2][2] * s[4] - a[2][3] * s[3],
+            ],
+            [
+                -a[1][0] * c[5] + a[1][2] * c[2] - a[1][3] * c[1],
+                a[0][0] * c[5] - a[0][2] * c[2] + a[0][3] * c[1],
+                -a[3][0] * s[5] + a[3][2] * s[2] - a[3][3] * s[1],
+                a[2][0] * s[5] - a[2][2] * s[2] + a[2][3] * s[1],
+            ],
+            [
+                a[1][0] * c[4] - a[1][1] * c[2] + a[1][3] * c[0],
+                -a[0][0] * c[4] + a[0][1] * c[2] - a[0][3] * c[0],
+                a[3][0] * s[4] - a[3][1] * s[2] + a[3][3] * s[0],
+                -a[2][0] * s[4] + a[2][1] * s[2] - a[2][3] * s[0],
+            ],
+            [
+                -a[1][0] * c[3] + a[1][1] * c[1] - a[1][2] * c[0],
+                a[0][0] * c[3] - a[0][1] * c[1] + a[0][2] * c[0],
+                -a[3][0] * s[3] + a[3][1] * s[1] - a[3][2] * s[0],
+                a[2][0] * s[3] - a[2][1] * s[1] + a[2][2] * s[0],
+            ],
+        ];
+        Some(Matrix::new(adjugate.map(|row| row.map(|v| v / det))))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub matrix: Matrix,
+    pub inverse: Matrix,
+    pub inverse_transpose: Matrix,
+}
+impl Transform {
+    pub fn new(matrix: Matrix) -> Result<Self, SingularMatrixError> {
+        let inverse = matrix.inverse().ok_or(SingularMatrixError {
+            determinant: matrix.determinant(),
+        })?;
+        Ok(Transform {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            matrix: Matrix::identity(),
+            inverse: Matrix::identity(),
+            inverse_transpose: Matrix::identity(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SingularMatrixError {
+    pub determinant: f64,
+}
+impl fmt::Display for SingularMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transform is not invertible")
+    }
+}
+impl std::error::Error for SingularMatrixError {}
+
 impl Index<(usize, usize)> for Matrix {
     type Output = f64;
-    fn index<'a>(&'a self, (y, x): (usize, usize)) -> &'a f64 {
+    fn index(&self, (y, x): (usize, usize)) -> &f64 {
         &self.m[y][x]
     }
 }
 
 impl IndexMut<(usize, usize)> for Matrix {
-    fn index_mut<'a>(&'a mut self, (y, x): (usize, usize)) -> &'a mut f64 {
+    fn index_mut(&mut self, (y, x): (usize, usize)) -> &mut f64 {
         &mut self.m[y][x]
     }
 }
@@ -196,25 +237,16 @@ impl ops::Mul<Matrix> for Matrix {
     type Output = Matrix;
 
     fn mul(self, _rhs: Matrix) -> Matrix {
-        let mut m = Matrix::new(self.shape.0, self.shape.1);
-        for r in 0..self.shape.0 as usize {
-            for c in 0.._rhs.shape.1 as usize {
-                m[(r, c)] = self[(r, 0)] * _rhs[(0, c)]
-                    + self[(r, 1)] * _rhs[(1, c)]
-                    + self[(r, 2)] * _rhs[(2, c)]
-                    + self[(r, 3)] * _rhs[(3, c)];
-            }
-        }
-        m
+        &self * &_rhs
     }
 }
 impl ops::Mul<&Matrix> for &Matrix {
     type Output = Matrix;
 
     fn mul(self, _rhs: &Matrix) -> Matrix {
-        let mut m = Matrix::new(self.shape.0, self.shape.1);
-        for r in 0..self.shape.0 as usize {
-            for c in 0.._rhs.shape.1 as usize {
+        let mut m = Matrix::new([[0.; 4]; 4]);
+        for r in 0..4 {
+            for c in 0..4 {
                 m[(r, c)] = self[(r, 0)] * _rhs[(0, c)]
                     + self[(r, 1)] * _rhs[(1, c)]
                     + self[(r, 2)] * _rhs[(2, c)]
@@ -227,24 +259,7 @@ impl ops::Mul<&Matrix> for &Matrix {
 impl ops::Mul<tuple::Tuple> for Matrix {
     type Output = tuple::Tuple;
     fn mul(self, _rhs: tuple::Tuple) -> tuple::Tuple {
-        tuple::Tuple {
-            x: self[(0, 0)] * _rhs.x
-                + self[(0, 1)] * _rhs.y
-                + self[(0, 2)] * _rhs.z
-                + self[(0, 3)] * _rhs.w,
-            y: self[(1, 0)] * _rhs.x
-                + self[(1, 1)] * _rhs.y
-                + self[(1, 2)] * _rhs.z
-                + self[(1, 3)] * _rhs.w,
-            z: self[(2, 0)] * _rhs.x
-                + self[(2, 1)] * _rhs.y
-                + self[(2, 2)] * _rhs.z
-                + self[(2, 3)] * _rhs.w,
-            w: self[(3, 0)] * _rhs.x
-                + self[(3, 1)] * _rhs.y
-                + self[(3, 2)] * _rhs.z
-                + self[(3, 3)] * _rhs.w,
-        }
+        &self * _rhs
     }
 }
 impl ops::Mul<tuple::Tuple> for &Matrix {
@@ -278,16 +293,12 @@ mod tests {
     use crate::tuple;
     #[test]
     fn test_matrix_4x4() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![5.5, 6.5, 7.5, 8.5],
-                vec![9., 10., 11., 12.],
-                vec![13.5, 14.5, 15.5, 16.5],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [5.5, 6.5, 7.5, 8.5],
+            [9., 10., 11., 12.],
+            [13.5, 14.5, 15.5, 16.5],
+        ]);
         assert_eq!(m[(0, 0)], 1.);
         assert_eq!(m[(0, 3)], 4.);
         assert_eq!(m[(1, 0)], 5.5);
@@ -297,121 +308,68 @@ mod tests {
         assert_eq!(m[(3, 2)], 15.5);
     }
     #[test]
-    fn test_matrix_2x2() {
-        let m = matrix::Matrix::from(2, 2, vec![vec![-3., 5.], vec![1., -2.]]);
-
-        assert_eq!(m[(0, 0)], -3.);
-        assert_eq!(m[(0, 1)], 5.);
-        assert_eq!(m[(1, 0)], 1.);
-        assert_eq!(m[(1, 1)], -2.);
-    }
-    #[test]
-    fn test_matrix_3x3() {
-        let m = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![-3., 5., 0.], vec![1., -2., -7.], vec![0., 1., 1.]],
-        );
-
-        assert_eq!(m[(0, 0)], -3.);
-        assert_eq!(m[(1, 1)], -2.);
-        assert_eq!(m[(2, 2)], 1.);
-    }
-    #[test]
     fn test_matrix_eq() {
-        let m1 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![5., 6., 7., 8.],
-                vec![9., 8., 7., 6.],
-                vec![5., 4., 3., 2.],
-            ],
-        );
-        let m2 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![5., 6., 7., 8.],
-                vec![9., 8., 7., 6.],
-                vec![5., 4., 3., 2.],
-            ],
-        );
+        let m1 = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 8., 7., 6.],
+            [5., 4., 3., 2.],
+        ]);
+        let m2 = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 8., 7., 6.],
+            [5., 4., 3., 2.],
+        ]);
         assert_eq!(m1, m2);
     }
     #[test]
     fn test_matrix_neq() {
-        let m1 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![5., 6., 7., 8.],
-                vec![9., 8., 7., 6.],
-                vec![5., 4., 3., 2.],
-            ],
-        );
-        let m2 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![2., 3., 4., 5.],
-                vec![6., 7., 8., 9.],
-                vec![8., 7., 6., 5.],
-                vec![4., 3., 2., 1.],
-            ],
-        );
+        let m1 = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 8., 7., 6.],
+            [5., 4., 3., 2.],
+        ]);
+        let m2 = matrix::Matrix::new([
+            [2., 3., 4., 5.],
+            [6., 7., 8., 9.],
+            [8., 7., 6., 5.],
+            [4., 3., 2., 1.],
+        ]);
         assert_ne!(m1, m2);
     }
 
     #[test]
     fn test_matrix_mul() {
-        let m1 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![5., 6., 7., 8.],
-                vec![9., 8., 7., 6.],
-                vec![5., 4., 3., 2.],
-            ],
-        );
-        let m2 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![-2., 1., 2., 3.],
-                vec![3., 2., 1., -1.],
-                vec![4., 3., 6., 5.],
-                vec![1., 2., 7., 8.],
-            ],
-        );
-        let expected = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![20., 22., 50., 48.],
-                vec![44., 54., 114., 108.],
-                vec![40., 58., 110., 102.],
-                vec![16., 26., 46., 42.],
-            ],
-        );
+        let m1 = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 8., 7., 6.],
+            [5., 4., 3., 2.],
+        ]);
+        let m2 = matrix::Matrix::new([
+            [-2., 1., 2., 3.],
+            [3., 2., 1., -1.],
+            [4., 3., 6., 5.],
+            [1., 2., 7., 8.],
+        ]);
+        let expected = matrix::Matrix::new([
+            [20., 22., 50., 48.],
+            [44., 54., 114., 108.],
+            [40., 58., 110., 102.],
+            [16., 26., 46., 42.],
+        ]);
         assert_eq!(m1 * m2, expected);
     }
     #[test]
     fn test_matrix_tuple_mul() {
-        let m1 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![1., 2., 3., 4.],
-                vec![2., 4., 4., 2.],
-                vec![8., 6., 4., 1.],
-                vec![0., 0., 0., 1.],
-            ],
-        );
+        let m1 = matrix::Matrix::new([
+            [1., 2., 3., 4.],
+            [2., 4., 4., 2.],
+            [8., 6., 4., 1.],
+            [0., 0., 0., 1.],
+        ]);
         let m2 = tuple::Tuple {
             x: 1.,
             y: 2.,
@@ -428,135 +386,54 @@ mod tests {
     }
     #[test]
     fn test_matrix_identity_mul() {
-        let m1 = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![0., 1., 2., 4.],
-                vec![1., 2., 4., 8.],
-                vec![2., 4., 8., 16.],
-                vec![4., 8., 16., 32.],
-            ],
-        );
+        let m1 = matrix::Matrix::new([
+            [0., 1., 2., 4.],
+            [1., 2., 4., 8.],
+            [2., 4., 8., 16.],
+            [4., 8., 16., 32.],
+        ]);
 
-        assert_eq!(m1.clone() * matrix::Matrix::identity(), m1);
-        assert_eq!(matrix::Matrix::identity() * m1.clone(), m1);
+        assert_eq!(m1 * matrix::Matrix::identity(), m1);
+        assert_eq!(matrix::Matrix::identity() * m1, m1);
     }
     #[test]
     fn test_matrix_transpose() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![0., 9., 3., 0.],
-                vec![9., 8., 0., 8.],
-                vec![1., 8., 5., 3.],
-                vec![0., 0., 5., 8.],
-            ],
-        );
-        let expected = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![0., 9., 1., 0.],
-                vec![9., 8., 8., 0.],
-                vec![3., 0., 5., 5.],
-                vec![0., 8., 3., 8.],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [0., 9., 3., 0.],
+            [9., 8., 0., 8.],
+            [1., 8., 5., 3.],
+            [0., 0., 5., 8.],
+        ]);
+        let expected = matrix::Matrix::new([
+            [0., 9., 1., 0.],
+            [9., 8., 8., 0.],
+            [3., 0., 5., 5.],
+            [0., 8., 3., 8.],
+        ]);
 
         assert_eq!(m.transpose(), expected);
     }
-    #[test]
-    fn test_matrix_determinant() {
-        let m = matrix::Matrix::from(2, 2, vec![vec![1., 5.], vec![-3., 2.]]);
-        assert_eq!(m.determinant(), 17.0);
-    }
-
-    #[test]
-    fn test_matrix_submatix_1() {
-        let m = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![1., 5., 0.], vec![-3., 2., 7.], vec![0., 6., -3.]],
-        );
-        let expected = matrix::Matrix::from(2, 2, vec![vec![-3., 2.], vec![0., 6.]]);
-        assert_eq!(m.submatrix(0, 2), expected);
-    }
 
     #[test]
     fn test_matrix_submatix_2() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![-6., 1., 1., 6.],
-                vec![-8., 5., 8., 6.],
-                vec![-1., 0., 8., 2.],
-                vec![-7., 1., -1., 1.],
-            ],
-        );
-        let expected = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![-6., 1., 6.], vec![-8., 8., 6.], vec![-7., -1., 1.]],
-        );
+        let m = matrix::Matrix::new([
+            [-6., 1., 1., 6.],
+            [-8., 5., 8., 6.],
+            [-1., 0., 8., 2.],
+            [-7., 1., -1., 1.],
+        ]);
+        let expected = [[-6., 1., 6.], [-8., 8., 6.], [-7., -1., 1.]];
         assert_eq!(m.submatrix(2, 1), expected);
     }
 
-    #[test]
-    fn test_matrix_minor() {
-        let m = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![3., 5., 0.], vec![2., -1., -7.], vec![6., -1., 5.]],
-        );
-        assert_eq!(m.submatrix(1, 0).determinant(), 25.);
-        assert_eq!(m.minor(1, 0), 25.);
-    }
-
-    #[test]
-    fn test_matrix_cofactor() {
-        let m = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![3., 5., 0.], vec![2., -1., -7.], vec![6., -1., 5.]],
-        );
-
-        assert_eq!(m.minor(0, 0), -12.);
-        assert_eq!(m.cofactor(0, 0), -12.);
-
-        assert_eq!(m.minor(1, 0), 25.);
-        assert_eq!(m.cofactor(1, 0), -25.);
-    }
-
-    #[test]
-    fn test_matrix_3x3_determinant() {
-        let m = matrix::Matrix::from(
-            3,
-            3,
-            vec![vec![1., 2., 6.], vec![-5., 8., -4.], vec![2., 6., 4.]],
-        );
-
-        assert_eq!(m.cofactor(0, 0), 56.);
-        assert_eq!(m.cofactor(0, 1), 12.);
-        assert_eq!(m.cofactor(0, 2), -46.);
-
-        assert_eq!(m.determinant(), -196.);
-    }
-
     #[test]
     fn test_matrix_4x4_determinant() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![-2., -8., 3., 5.],
-                vec![-3., 1., 7., 3.],
-                vec![1., 2., -9., 6.],
-                vec![-6., 7., 7., -9.],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [-2., -8., 3., 5.],
+            [-3., 1., 7., 3.],
+            [1., 2., -9., 6.],
+            [-6., 7., 7., -9.],
+        ]);
 
         assert_eq!(m.cofactor(0, 0), 690.);
         assert_eq!(m.cofactor(0, 1), 447.);
@@ -567,58 +444,42 @@ mod tests {
 
     #[test]
     fn test_matrix_is_inversible_1() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![6., 4., 4., 4.],
-                vec![5., 5., 7., 6.],
-                vec![4., -9., 3., -7.],
-                vec![9., 1., 7., -6.],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [6., 4., 4., 4.],
+            [5., 5., 7., 6.],
+            [4., -9., 3., -7.],
+            [9., 1., 7., -6.],
+        ]);
 
         assert_eq!(m.is_inversible(), true);
     }
 
     #[test]
     fn test_matrix_is_inversible_2() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![-4., 2., -2., -3.],
-                vec![9., 6., 2., 6.],
-                vec![0., -5., 1., -5.],
-                vec![0., 0., 0., -0.],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [-4., 2., -2., -3.],
+            [9., 6., 2., 6.],
+            [0., -5., 1., -5.],
+            [0., 0., 0., -0.],
+        ]);
 
         assert_eq!(m.is_inversible(), false);
     }
 
     #[test]
     fn test_matrix_inverse() {
-        let m = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![-5., 2., 6., -8.],
-                vec![1., -5., 1., 8.],
-                vec![7., 7., -6., -7.],
-                vec![1., -3., 7., 4.],
-            ],
-        );
-        let expected = matrix::Matrix::from(
-            4,
-            4,
-            vec![
-                vec![0.21805, 0.45113, 0.24060, -0.04511],
-                vec![-0.80827, -1.45677, -0.44361, 0.52068],
-                vec![-0.07895, -0.22368, -0.05263, 0.19737],
-                vec![-0.52256, -0.81391, -0.30075, 0.30639],
-            ],
-        );
+        let m = matrix::Matrix::new([
+            [-5., 2., 6., -8.],
+            [1., -5., 1., 8.],
+            [7., 7., -6., -7.],
+            [1., -3., 7., 4.],
+        ]);
+        let expected = matrix::Matrix::new([
+            [0.21805, 0.45113, 0.24060, -0.04511],
+            [-0.80827, -1.45677, -0.44361, 0.52068],
+            [-0.07895, -0.22368, -0.05263, 0.19737],
+            [-0.52256, -0.81391, -0.30075, 0.30639],
+        ]);
         let inv = m.inverse().expect("Inverse should be found");
 
         assert_eq!(m.determinant(), 532.);
@@ -816,4 +677,103 @@ mod tests {
         assert!(p4.y - (-0.) < f64::EPSILON);
         assert!(p4.z - 7. < f64::EPSILON);
     }
+
+    #[test]
+    fn test_view_transform_default() {
+        let from = tuple::Tuple::point(0., 0., 0.);
+        let to = tuple::Tuple::point(0., 0., -1.);
+        let up = tuple::Tuple::vector(0., 1., 0.);
+
+        assert_eq!(
+            matrix::Matrix::view_transform(from, to, up),
+            matrix::Matrix::identity()
+        );
+    }
+    #[test]
+    fn test_view_transform_positive_z() {
+        let from = tuple::Tuple::point(0., 0., 0.);
+        let to = tuple::Tuple::point(0., 0., 1.);
+        let up = tuple::Tuple::vector(0., 1., 0.);
+
+        assert_eq!(
+            matrix::Matrix::view_transform(from, to, up),
+            matrix::Matrix::scale(-1., 1., -1.)
+        );
+    }
+    #[test]
+    fn test_view_transform_moves_world() {
+        let from = tuple::Tuple::point(0., 0., 8.);
+        let to = tuple::Tuple::point(0., 0., 0.);
+        let up = tuple::Tuple::vector(0., 1., 0.);
+
+        assert_eq!(
+            matrix::Matrix::view_transform(from, to, up),
+            matrix::Matrix::translation(0., 0., -8.)
+        );
+    }
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let from = tuple::Tuple::point(1., 3., 2.);
+        let to = tuple::Tuple::point(4., -2., 8.);
+        let up = tuple::Tuple::vector(1., 1., 0.);
+        let t = matrix::Matrix::view_transform(from, to, up);
+        let expected = matrix::Matrix::new([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.],
+            [0., 0., 0., 1.],
+        ]);
+        for r in 0..4 {
+            for c in 0..4 {
+                assert!((t[(r, c)] - expected[(r, c)]).abs() < 0.0001)
+            }
+        }
+    }
+
+    #[test]
+    fn test_matrix_inverse_products() {
+        let a = matrix::Matrix::new([
+            [3., -9., 7., 3.],
+            [3., -8., 2., -9.],
+            [-4., 4., 4., 1.],
+            [-6., 5., -1., 1.],
+        ]);
+        let b = matrix::Matrix::new([
+            [8., 2., 2., 2.],
+            [3., -1., 7., 0.],
+            [7., 0., 5., 4.],
+            [6., -2., 0., 5.],
+        ]);
+        let c = a * b;
+        let back = c * b.inverse().expect("Should inverse");
+        let identity = a * a.inverse().expect("Should inverse");
+        for r in 0..4 {
+            for col in 0..4 {
+                assert!((back[(r, col)] - a[(r, col)]).abs() < 0.00001);
+                let expected = if r == col { 1. } else { 0. };
+                assert!((identity[(r, col)] - expected).abs() < 0.00001);
+            }
+        }
+        assert_eq!(matrix::Matrix::scale(1., 0., 1.).inverse(), None);
+    }
+
+    #[test]
+    fn test_transform_caches_inverses() {
+        let m = matrix::Matrix::translation(1., 2., 3.) * matrix::Matrix::scale(2., 4., 8.);
+        let t = matrix::Transform::new(m).expect("Transform should be invertible");
+        assert_eq!(t.matrix, m);
+        assert_eq!(t.inverse, m.inverse().expect("Should inverse"));
+        assert_eq!(t.inverse_transpose, t.inverse.transpose());
+        assert_eq!(
+            matrix::Transform::default().inverse,
+            matrix::Matrix::identity()
+        );
+
+        let singular = matrix::Matrix::scale(0., 1., 1.);
+        let err = matrix::Transform::new(singular).expect_err("Should reject a singular matrix");
+        assert_eq!(err.determinant, 0.);
+        assert_eq!(err.to_string(), "transform is not invertible");
+        let nan = matrix::Matrix::scale(f64::NAN, 1., 1.);
+        assert!(matrix::Transform::new(nan).is_err());
+    }
 }
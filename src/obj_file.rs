@@ -0,0 +1,392 @@
+use std::fmt;
+
+use crate::{
+    group::Group,
+    object::Shape,
+    triangle::{SmoothTriangle, Triangle},
+    tuple::Tuple,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct ObjError {
+    pub line: usize,
+    pub message: String,
+}
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl std::error::Error for ObjError {}
+
+#[derive(Debug)]
+pub struct ObjFile {
+    pub vertices: Vec<Tuple>,
+    pub normals: Vec<Tuple>,
+    pub textures: Vec<Tuple>,
+    pub ignored_lines: Vec<usize>,
+    pub default_group: Group,
+    pub groups: Vec<(String, Group)>,
+}
+impl ObjFile {
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|(n, _)| n == name).map(|(_, g)| g)
+    }
+
+    pub fn to_group(self) -> Group {
+        let mut g = self.default_group;
+        for (_, named) in self.groups {
+            g.add_child(Box::new(named));
+        }
+        g
+    }
+
+    fn current_group(&mut self, name: &Option<String>) -> &mut Group {
+        match name {
+            None => &mut self.default_group,
+            Some(name) => {
+                let pos = match self.groups.iter().position(|(n, _)| n == name) {
+                    Some(pos) => pos,
+                    None => {
+                        self.groups.push((name.clone(), Group::new()));
+                        self.groups.len() - 1
+                    }
+                };
+                &mut self.groups[pos].1
+            }
+        }
+    }
+}
+
+fn parse_floats<const N: usize>(
+    args: &[&str],
+    line: usize,
+    record: &str,
+) -> Result<[f64; N], ObjError> {
+    if args.len() < N {
+        return Err(ObjError {
+            line,
+            message: format!("'{record}' needs {N} values, found {}", args.len()),
+        });
+    }
+    let mut values = [0.; N];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg.parse().map_err(|_| ObjError {
+            line,
+            message: format!("invalid number '{arg}' in '{record}' record"),
+        })?;
+    }
+    Ok(values)
+}
+
+fn resolve_index(arg: &str, count: usize, line: usize, kind: &str) -> Result<usize, ObjError> {
+    let invalid = || ObjError {
+        line,
+        message: format!("invalid {kind} index '{arg}'"),
+    };
+    let index: i64 = arg.parse().map_err(|_| invalid())?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(ObjError {
+            line,
+            message: format!("{kind} index {index} out of range (1..={count})"),
+        });
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_face(obj: &ObjFile, args: &[&str], line: usize) -> Result<Vec<Box<dyn Shape>>, ObjError> {
+    if args.len() < 3 {
+        return Err(ObjError {
+            line,
+            message: format!("face needs at least 3 vertices, found {}", args.len()),
+        });
+    }
+    let mut points = vec![];
+    let mut normals = vec![];
+    for arg in args {
+        let mut parts = arg.split('/');
+        let v = parts.next().unwrap_or("");
+        points.push(obj.vertices[resolve_index(v, obj.vertices.len(), line, "vertex")?]);
+        if let Some(vt) = parts.next() {
+            if !vt.is_empty() {
+                resolve_index(vt, obj.textures.len(), line, "texture")?;
+            }
+        }
+        if let Some(vn) = parts.next() {
+            if !vn.is_empty() {
+                normals.push(obj.normals[resolve_index(vn, obj.normals.len(), line, "normal")?]);
+            }
+        }
+    }
+    let smooth = normals.len() == points.len();
+    Ok((1..points.len() - 1)
+        .map(|i| -> Box<dyn Shape> {
+            if smooth {
+                Box::new(SmoothTriangle::new(
+                    points[0],
+                    points[i],
+                    points[i + 1],
+                    normals[0],
+                    normals[i],
+                    normals[i + 1],
+                ))
+            } else {
+                Box::new(Triangle::new(points[0], points[i], points[i + 1]))
+            }
+        })
+        .collect())
+}
+
+pub fn parse_obj_file(input: &str) -> Result<ObjFile, ObjError> {
+    let mut obj = ObjFile {
+        vertices: vec![],
+        normals: vec![],
+        textures: vec![],
+        ignored_lines: vec![],
+        default_group: Group::new(),
+        groups: vec![],
+    };
+    let mut current: Option<String> = None;
+    for (pos, text) in input.lines().enumerate() {
+        let line = pos + 1;
+        let mut words = text.split_whitespace();
+        let Some(record) = words.next() else {
+            continue;
+        };
+        let args: Vec<&str> = words.collect();
+        match record {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(&args, line, record)?;
+                obj.vertices.push(Tuple::point(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&args, line, record)?;
+                obj.normals.push(Tuple::vector(x, y, z));
+            }
+            "vt" => {
+                let [u] = parse_floats::<1>(&args, line, record)?;
+                let v = match args.len() {
+                    1 => 0.,
+                    _ => parse_floats::<2>(&args, line, record)?[1],
+                };
+                obj.textures.push(Tuple::point(u, v, 0.));
+            }
+            "f" => {
+                let triangles = parse_face(&obj, &args, line)?;
+                let group = obj.current_group(&current);
+                for t in triangles {
+                    group.add_child(t);
+                }
+            }
+            "g" => {
+                if args.is_empty() {
+                    return Err(ObjError {
+                        line,
+                        message: "group needs a name".to_string(),
+                    });
+                }
+                current = Some(args.join(" "));
+                obj.current_group(&current);
+            }
+            _ => obj.ignored_lines.push(line),
+        }
+    }
+    Ok(obj)
+}
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::{
+        object::Shape,
+        triangle::{SmoothTriangle, Triangle},
+        tuple::Tuple,
+    };
+
+    use super::{parse_obj_file, ObjError};
+
+    fn as_triangle(s: &dyn Shape) -> &Triangle {
+        (s as &dyn Any)
+            .downcast_ref::<Triangle>()
+            .expect("Should be a triangle")
+    }
+
+    #[test]
+    fn test_ignore_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let obj = parse_obj_file(gibberish).expect("Should parse");
+        assert_eq!(obj.ignored_lines, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_vertex_records() {
+        let file = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+        let obj = parse_obj_file(file).expect("Should parse");
+        assert_eq!(obj.vertices[0], Tuple::point(-1., 1., 0.));
+        assert_eq!(obj.vertices[1], Tuple::point(-1., 0.5, 0.));
+        assert_eq!(obj.vertices[2], Tuple::point(1., 0., 0.));
+        assert_eq!(obj.vertices[3], Tuple::point(1., 1., 0.));
+    }
+
+    #[test]
+    fn test_triangle_faces() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let obj = parse_obj_file(file).expect("Should parse");
+        let children = obj.default_group.children();
+        let t1 = as_triangle(children[0].as_ref());
+        let t2 = as_triangle(children[1].as_ref());
+        assert_eq!(t1.p1, obj.vertices[0]);
+        assert_eq!(t1.p2, obj.vertices[1]);
+        assert_eq!(t1.p3, obj.vertices[2]);
+        assert_eq!(t2.p1, obj.vertices[0]);
+        assert_eq!(t2.p2, obj.vertices[2]);
+        assert_eq!(t2.p3, obj.vertices[3]);
+    }
+
+    #[test]
+    fn test_triangulate_polygons() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let obj = parse_obj_file(file).expect("Should parse");
+        let children = obj.default_group.children();
+        assert_eq!(children.len(), 3);
+        let t3 = as_triangle(children[2].as_ref());
+        assert_eq!(t3.p1, obj.vertices[0]);
+        assert_eq!(t3.p2, obj.vertices[3]);
+        assert_eq!(t3.p3, obj.vertices[4]);
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj_file(file).expect("Should parse");
+        let g1 = obj.group("FirstGroup").expect("Should have group");
+        let g2 = obj.group("SecondGroup").expect("Should have group");
+        let t1 = as_triangle(g1.children()[0].as_ref());
+        let t2 = as_triangle(g2.children()[0].as_ref());
+        assert_eq!(t1.p3, obj.vertices[2]);
+        assert_eq!(t2.p2, obj.vertices[2]);
+        assert_eq!(t2.p3, obj.vertices[3]);
+    }
+
+    #[test]
+    fn test_to_group() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 4
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let g = parse_obj_file(file).expect("Should parse").to_group();
+        assert_eq!(g.children().len(), 3);
+    }
+
+    #[test]
+    fn test_vertex_normals_and_textures() {
+        let file = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3
+vt 0.5 0.25";
+        let obj = parse_obj_file(file).expect("Should parse");
+        assert_eq!(obj.normals[0], Tuple::vector(0., 0., 1.));
+        assert_eq!(obj.normals[1], Tuple::vector(0.707, 0., -0.707));
+        assert_eq!(obj.normals[2], Tuple::vector(1., 2., 3.));
+        assert_eq!(obj.textures[0], Tuple::point(0.5, 0.25, 0.));
+    }
+
+    #[test]
+    fn test_faces_with_normals() {
+        let file = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+vt 0 0
+
+f 1//3 2//1 3//2
+f 1/1/3 2/1/1 3/1/2";
+        let obj = parse_obj_file(file).expect("Should parse");
+        let children = obj.default_group.children();
+        for child in children {
+            let t = (child.as_ref() as &dyn Any)
+                .downcast_ref::<SmoothTriangle>()
+                .expect("Should be a smooth triangle");
+            assert_eq!(t.p1, obj.vertices[0]);
+            assert_eq!(t.p2, obj.vertices[1]);
+            assert_eq!(t.p3, obj.vertices[2]);
+            assert_eq!(t.n1, obj.normals[2]);
+            assert_eq!(t.n2, obj.normals[0]);
+            assert_eq!(t.n3, obj.normals[1]);
+        }
+    }
+
+    #[test]
+    fn test_negative_indices() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1";
+        let obj = parse_obj_file(file).expect("Should parse");
+        let t = as_triangle(obj.default_group.children()[0].as_ref());
+        assert_eq!(t.p1, obj.vertices[0]);
+        assert_eq!(t.p3, obj.vertices[2]);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let err = parse_obj_file("v 1 2 3\nv 1 x 3").expect_err("Should fail");
+        assert_eq!(err.line, 2);
+
+        let err = parse_obj_file("v 1 2 3\nv 1 2 4\nv 1 3 3\n\nf 1 2 7").expect_err("Should fail");
+        assert_eq!(
+            err,
+            ObjError {
+                line: 5,
+                message: "vertex index 7 out of range (1..=3)".to_string()
+            }
+        );
+
+        let err = parse_obj_file("v 1 2 3\nf 1 1").expect_err("Should fail");
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.to_string(),
+            "line 2: face needs at least 3 vertices, found 2"
+        );
+    }
+}
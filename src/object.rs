@@ -1,38 +1,97 @@
-use crate::{material::Material, matrix::Matrix, tuple::Tuple};
+use std::any::Any;
+use std::fmt::Debug;
 
-#[derive(PartialEq, PartialOrd, Debug, Clone)]
-pub enum Object {
-    Sphere(Matrix, Material),
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::Intersection,
+    material::Material,
+    matrix::{Matrix, SingularMatrixError, Transform},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeData {
+    pub transform: Transform,
+    pub material: Material,
 }
-impl Object {
-    pub fn sphere() -> Self {
-        Object::Sphere(Matrix::identity(), Material::default())
+impl ShapeData {
+    pub fn new() -> Self {
+        ShapeData::with_mat(Material::default())
+    }
+    pub fn with_mat(material: Material) -> Self {
+        ShapeData {
+            transform: Transform::default(),
+            material,
+        }
     }
-    pub fn sphere_with_mat(mat: Material) -> Self {
-        Object::Sphere(Matrix::identity(), mat)
+}
+impl Default for ShapeData {
+    fn default() -> Self {
+        ShapeData::new()
     }
 }
-impl Eq for Object {}
 
-pub fn transform(o: &Object, m: &Matrix) -> Object {
-    match o {
-        Object::Sphere(s, mat) => Object::Sphere(s * m, *mat),
+pub trait Shape: Any + Debug + Send + Sync {
+    fn data(&self) -> &ShapeData;
+    fn data_mut(&mut self) -> &mut ShapeData;
+    fn transform(&self) -> &Matrix {
+        &self.data().transform.matrix
+    }
+    fn set_transform(&mut self, m: Matrix) -> Result<(), SingularMatrixError> {
+        self.data_mut().transform = Transform::new(m)?;
+        Ok(())
+    }
+    fn inverse(&self) -> &Matrix {
+        &self.data().transform.inverse
+    }
+    fn inverse_transpose(&self) -> &Matrix {
+        &self.data().transform.inverse_transpose
+    }
+    fn material(&self) -> &Material {
+        &self.data().material
+    }
+    fn set_material(&mut self, m: Material) {
+        self.data_mut().material = m;
+    }
+    fn local_intersect(&self, r: &Ray) -> Intersections<'_>;
+    fn local_normal_at(&self, object_point: Tuple, hit: &Intersection) -> Tuple;
+    fn bounds(&self) -> BoundingBox;
+    fn divide(&mut self, _threshold: usize) {}
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other)
     }
 }
 
-pub fn normal_at(o: &Object, world_point: Tuple) -> Tuple {
-    let object_transform = match o {
-        Object::Sphere(transform, _) => transform,
-    };
-    let object_point = object_transform.inverse().expect("Should inverse") * world_point;
-    let object_normal = object_point - Tuple::point(0., 0., 0.);
-    let mut world_normal = object_transform
-        .inverse()
-        .expect("Should inverse")
-        .transpose()
-        * object_normal;
-    world_normal.to_vector();
-    world_normal.normalize()
+impl PartialEq for dyn Shape + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+pub fn world_to_object(o: &dyn Shape, parents: &[&dyn Shape], world_point: Tuple) -> Tuple {
+    parents
+        .iter()
+        .rev()
+        .chain([&o])
+        .fold(world_point, |point, s| s.inverse() * point)
+}
+
+pub fn normal_to_world(o: &dyn Shape, parents: &[&dyn Shape], object_normal: Tuple) -> Tuple {
+    [&o].into_iter()
+        .chain(parents.iter())
+        .fold(object_normal, |normal, s| {
+            let mut world_normal = s.inverse_transpose() * normal;
+            world_normal.to_vector();
+            world_normal.normalize()
+        })
+}
+
+pub fn normal_at(o: &dyn Shape, world_point: Tuple, hit: &Intersection) -> Tuple {
+    let object_point = world_to_object(o, &hit.parents, world_point);
+    let object_normal = o.local_normal_at(object_point, hit);
+    normal_to_world(o, &hit.parents, object_normal)
 }
 
 pub fn reflect(in_vector: &Tuple, normal: &Tuple) -> Tuple {
@@ -40,95 +99,158 @@ pub fn reflect(in_vector: &Tuple, normal: &Tuple) -> Tuple {
 }
 #[cfg(test)]
 mod tests {
-    use std::f64::consts::PI;
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+    use std::sync::Mutex;
 
-    use crate::{material::Material, matrix::Matrix, object::transform, tuple::Tuple};
+    use crate::{
+        bounds::BoundingBox,
+        intersect::{intersect, Intersections},
+        intersection::Intersection,
+        material::Material,
+        matrix::Matrix,
+        ray::Ray,
+        tuple::Tuple,
+    };
 
-    use super::{normal_at, reflect, Object};
+    use super::{normal_at, reflect, Shape, ShapeData};
 
-    #[test]
-    fn default_transformation() {
-        let a = Object::Sphere(Matrix::identity(), Material::default());
-        match a {
-            Object::Sphere(a, mat) => {
-                assert_eq!(a, Matrix::identity());
-                assert_eq!(mat, Material::default())
+    #[derive(Debug)]
+    struct TestShape {
+        data: ShapeData,
+        saved_ray: Mutex<Option<(Tuple, Tuple)>>,
+    }
+    impl TestShape {
+        fn new() -> Self {
+            TestShape {
+                data: ShapeData::new(),
+                saved_ray: Mutex::new(None),
             }
         }
     }
+    impl Shape for TestShape {
+        fn data(&self) -> &ShapeData {
+            &self.data
+        }
+        fn data_mut(&mut self) -> &mut ShapeData {
+            &mut self.data
+        }
+        fn local_intersect(&self, r: &Ray) -> Intersections<'_> {
+            *self.saved_ray.lock().expect("Should lock") = Some((r.origin, r.direction));
+            Intersections::new()
+        }
+        fn local_normal_at(&self, object_point: Tuple, _hit: &Intersection) -> Tuple {
+            Tuple::vector(object_point.x, object_point.y, object_point.z)
+        }
+        fn bounds(&self) -> BoundingBox {
+            BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+        }
+    }
 
     #[test]
-    fn default_translation() {
-        let o = Object::sphere();
-        let m = Matrix::translation(2., 3., 4.);
-        let o = transform(&o, &m);
-        match o {
-            Object::Sphere(a, mat) => {
-                assert_eq!(a, m);
-                assert_eq!(mat, Material::default())
-            }
-        }
+    fn test_default_transformation() {
+        let s = TestShape::new();
+        assert_eq!(*s.transform(), Matrix::identity());
+    }
+
+    #[test]
+    fn test_assign_transformation() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::translation(2., 3., 4.))
+            .expect("Transform should be invertible");
+        assert_eq!(*s.transform(), Matrix::translation(2., 3., 4.));
+    }
+
+    #[test]
+    fn test_assign_singular_transformation() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::translation(2., 3., 4.))
+            .expect("Transform should be invertible");
+        let err = s
+            .set_transform(Matrix::scale(0., 1., 1.))
+            .expect_err("Should reject a singular transform");
+        assert_eq!(err.determinant, 0.);
+        assert_eq!(*s.transform(), Matrix::translation(2., 3., 4.));
     }
 
     #[test]
-    fn test_normal_at_1() {
-        let s = Object::sphere();
-        let n = normal_at(&s, Tuple::point(1., 0., 0.));
-        assert_eq!(n, Tuple::vector(1., 0., 0.));
+    fn test_default_material() {
+        let s = TestShape::new();
+        assert_eq!(*s.material(), Material::default());
     }
 
     #[test]
-    fn test_normal_at_2() {
-        let s = Object::sphere();
-        let n = normal_at(&s, Tuple::point(0., 1., 0.));
-        assert_eq!(n, Tuple::vector(0., 1., 0.));
+    fn test_assign_material() {
+        let mut s = TestShape::new();
+        let mut m = Material::default();
+        m.ambiant = 1.;
+        s.set_material(m.clone());
+        assert_eq!(*s.material(), m);
     }
 
     #[test]
-    fn test_normal_at_3() {
-        let s = Object::sphere();
-        let n = normal_at(&s, Tuple::point(0., 0., 1.));
-        assert_eq!(n, Tuple::vector(0., 0., 1.));
+    fn test_intersect_scaled_shape() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        intersect(&r, &s);
+        let saved = s
+            .saved_ray
+            .lock()
+            .expect("Should lock")
+            .expect("Should save ray");
+        assert_eq!(saved.0, Tuple::point(0., 0., -2.5));
+        assert_eq!(saved.1, Tuple::vector(0., 0., 0.5));
     }
 
     #[test]
-    fn test_normal_at_4() {
-        let s = Object::sphere();
+    fn test_intersect_translated_shape() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::translation(5., 0., 0.))
+            .expect("Transform should be invertible");
+        intersect(&r, &s);
+        let saved = s
+            .saved_ray
+            .lock()
+            .expect("Should lock")
+            .expect("Should save ray");
+        assert_eq!(saved.0, Tuple::point(-5., 0., -5.));
+        assert_eq!(saved.1, Tuple::vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_normal_on_a_translate_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::translation(0., 1., 0.))
+            .expect("Transform should be invertible");
         let n = normal_at(
             &s,
-            Tuple::point(f64::sqrt(3.) / 3., f64::sqrt(3.) / 3., f64::sqrt(3.) / 3.),
-        );
-        assert_eq!(
-            n,
-            Tuple::vector(f64::sqrt(3.) / 3., f64::sqrt(3.) / 3., f64::sqrt(3.) / 3.)
+            Tuple::point(0., 1. + FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+            &Intersection::new(0., &s),
         );
+        let expected = Tuple::vector(0., FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        assert!((n.x - expected.x).abs() < 0.001);
+        assert!((n.y - expected.y).abs() < 0.001);
+        assert!((n.z - expected.z).abs() < 0.001);
+        assert!((n.w - expected.w).abs() < 0.001);
     }
 
     #[test]
-    fn test_normal_on_a_translate_sphere() {
-        let s = Object::sphere();
-        let s = transform(&s, &Matrix::translation(0., 1., 0.));
-        let n = normal_at(&s, Tuple::point(0., 1.70711, -0.70711));
-        let expected = Tuple::vector(0., 0.70711, -0.70711);
-        assert!(n.x - expected.x < 0.001);
-        assert!(n.y - expected.y < 0.001);
-        assert!(n.z - expected.z < 0.001);
-        assert!(n.w - expected.w < 0.001);
-    }
-    #[test]
-    fn test_normal_on_a_scale_sphere() {
-        let s = Object::sphere();
-        let m = Matrix::scale(1., 0.5, 1.) * Matrix::rot_z(PI / 5.);
-        let s = transform(&s, &m);
+    fn test_normal_on_a_transformed_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix::scale(1., 0.5, 1.) * Matrix::rot_z(PI / 5.))
+            .expect("Transform should be invertible");
         let n = normal_at(
             &s,
             Tuple::point(0., f64::sqrt(2.) / 2., -f64::sqrt(2.) / 2.),
+            &Intersection::new(0., &s),
         );
         let expected = Tuple::vector(0., 0.97014, -0.24254);
-        assert!(n.x - expected.x < 0.001);
-        assert!(n.y - expected.y < 0.001);
-        assert!(n.z - expected.z < 0.001);
-        assert!(n.w - expected.w < 0.001);
+        assert!((n.x - expected.x).abs() < 0.001);
+        assert!((n.y - expected.y).abs() < 0.001);
+        assert!((n.z - expected.z).abs() < 0.001);
+        assert!((n.w - expected.w).abs() < 0.001);
     }
 
     #[test]
@@ -149,17 +271,4 @@ mod tests {
         assert!(r.z - expected.z < 0.001);
         assert!(r.w - expected.w < 0.001);
     }
-
-    #[test]
-    fn test_objecti_with_mat() {
-        let mut mat = Material::default();
-        mat.ambiant = 1.;
-        let s = Object::sphere_with_mat(mat);
-
-        match s {
-            Object::Sphere(_, mmat) => {
-                assert_eq!(mmat, mat)
-            }
-        }
-    }
 }
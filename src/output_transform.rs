@@ -0,0 +1,134 @@
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    None,
+    Reinhard,
+    Aces,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Linear,
+    Srgb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputTransform {
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+    pub encoding: Encoding,
+}
+impl OutputTransform {
+    pub fn new(exposure: f64, tone_map: ToneMap, encoding: Encoding) -> Self {
+        OutputTransform {
+            exposure,
+            tone_map,
+            encoding,
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let map = |c: f64| {
+            let c = c * self.exposure;
+            let c = match self.tone_map {
+                ToneMap::None => c,
+                ToneMap::Reinhard => reinhard(c),
+                ToneMap::Aces => aces(c),
+            };
+            match self.encoding {
+                Encoding::Linear => c,
+                Encoding::Srgb => linear_to_srgb(c),
+            }
+        };
+        Color::new(map(color.r), map(color.g), map(color.b))
+    }
+}
+impl Default for OutputTransform {
+    fn default() -> Self {
+        OutputTransform::new(1., ToneMap::None, Encoding::Linear)
+    }
+}
+
+pub fn reinhard(c: f64) -> f64 {
+    let c = c.max(0.);
+    c / (1. + c)
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic reference curve.
+pub fn aces(c: f64) -> f64 {
+    let c = c.max(0.);
+    let mapped = (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14);
+    mapped.clamp(0., 1.)
+}
+
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::{aces, linear_to_srgb, reinhard, Encoding, OutputTransform, ToneMap};
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    #[test]
+    fn test_default_is_identity() {
+        let c = Color::new(1.9, -0.5, 0.25);
+        assert_eq!(OutputTransform::default().apply(c), c);
+    }
+
+    #[test]
+    fn test_exposure_scales_color() {
+        let t = OutputTransform::new(2., ToneMap::None, Encoding::Linear);
+        assert_eq!(t.apply(Color::new(0.25, 0.5, 1.)), Color::new(0.5, 1., 2.));
+    }
+
+    #[test]
+    fn test_reinhard() {
+        assert_eq!(reinhard(0.), 0.);
+        assert_eq!(reinhard(1.), 0.5);
+        assert_eq!(reinhard(3.), 0.75);
+        assert_eq!(reinhard(-1.), 0.);
+        assert!(reinhard(1e9) < 1.);
+    }
+
+    #[test]
+    fn test_aces() {
+        assert!(approx(aces(0.), 0.));
+        assert!(approx(aces(0.18), 0.26690));
+        assert!(approx(aces(1.), 0.8038));
+        assert_eq!(aces(100.), 1.);
+        let mut previous = 0.;
+        for i in 1..100 {
+            let v = aces(i as f64 * 0.1);
+            assert!(v >= previous);
+            previous = v;
+        }
+    }
+
+    #[test]
+    fn test_linear_to_srgb() {
+        assert_eq!(linear_to_srgb(0.), 0.);
+        assert!(approx(linear_to_srgb(1.), 1.));
+        assert!(approx(linear_to_srgb(0.002), 0.02584));
+        assert!(approx(linear_to_srgb(0.18), 0.46135));
+        assert!(approx(linear_to_srgb(0.5), 0.73536));
+    }
+
+    #[test]
+    fn test_apply_order() {
+        let t = OutputTransform::new(2., ToneMap::Reinhard, Encoding::Srgb);
+        let c = t.apply(Color::new(0.5, 0., 1.5));
+        assert!(approx(c.r, linear_to_srgb(0.5)));
+        assert_eq!(c.g, 0.);
+        assert!(approx(c.b, linear_to_srgb(0.75)));
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt::Debug;
+
+use crate::{
+    color::Color,
+    matrix::{Matrix, SingularMatrixError, Transform},
+    perlin::noise,
+    tuple::Tuple,
+};
+
+pub trait Pattern: Debug + Send + Sync {
+    fn transform_data(&self) -> &Transform;
+    fn transform_data_mut(&mut self) -> &mut Transform;
+    fn transform(&self) -> &Matrix {
+        &self.transform_data().matrix
+    }
+    fn set_transform(&mut self, m: Matrix) -> Result<(), SingularMatrixError> {
+        *self.transform_data_mut() = Transform::new(m)?;
+        Ok(())
+    }
+    fn inverse(&self) -> &Matrix {
+        &self.transform_data().inverse
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color;
+}
+
+pub fn pattern_at(pattern: &dyn Pattern, object_point: Tuple) -> Color {
+    let pattern_point = pattern.inverse() * object_point;
+    pattern.local_pattern_at(pattern_point)
+}
+
+fn is_even(v: f64) -> bool {
+    (v.floor() as i64).rem_euclid(2) == 0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solid {
+    transform: Transform,
+    pub color: Color,
+}
+impl Solid {
+    pub fn new(color: Color) -> Self {
+        Solid {
+            transform: Transform::default(),
+            color,
+        }
+    }
+}
+impl Pattern for Solid {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, _pattern_point: Tuple) -> Color {
+        self.color
+    }
+}
+
+#[derive(Debug)]
+pub struct Stripe {
+    transform: Transform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+impl Stripe {
+    pub fn new(a: Color, b: Color) -> Self {
+        Stripe::nested(Box::new(Solid::new(a)), Box::new(Solid::new(b)))
+    }
+    pub fn nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        Stripe {
+            transform: Transform::default(),
+            a,
+            b,
+        }
+    }
+}
+impl Pattern for Stripe {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        if is_even(pattern_point.x) {
+            pattern_at(self.a.as_ref(), pattern_point)
+        } else {
+            pattern_at(self.b.as_ref(), pattern_point)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gradient {
+    transform: Transform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+impl Gradient {
+    pub fn new(a: Color, b: Color) -> Self {
+        Gradient::nested(Box::new(Solid::new(a)), Box::new(Solid::new(b)))
+    }
+    pub fn nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        Gradient {
+            transform: Transform::default(),
+            a,
+            b,
+        }
+    }
+}
+impl Pattern for Gradient {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        let a = pattern_at(self.a.as_ref(), pattern_point);
+        let b = pattern_at(self.b.as_ref(), pattern_point);
+        let fraction = pattern_point.x - pattern_point.x.floor();
+        a + (b - a) * fraction
+    }
+}
+
+#[derive(Debug)]
+pub struct Ring {
+    transform: Transform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+impl Ring {
+    pub fn new(a: Color, b: Color) -> Self {
+        Ring::nested(Box::new(Solid::new(a)), Box::new(Solid::new(b)))
+    }
+    pub fn nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        Ring {
+            transform: Transform::default(),
+            a,
+            b,
+        }
+    }
+}
+impl Pattern for Ring {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        let distance =
+            (pattern_point.x * pattern_point.x + pattern_point.z * pattern_point.z).sqrt();
+        if is_even(distance) {
+            pattern_at(self.a.as_ref(), pattern_point)
+        } else {
+            pattern_at(self.b.as_ref(), pattern_point)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Checker {
+    transform: Transform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+impl Checker {
+    pub fn new(a: Color, b: Color) -> Self {
+        Checker::nested(Box::new(Solid::new(a)), Box::new(Solid::new(b)))
+    }
+    pub fn nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        Checker {
+            transform: Transform::default(),
+            a,
+            b,
+        }
+    }
+}
+impl Pattern for Checker {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        let sum = pattern_point.x.floor() + pattern_point.y.floor() + pattern_point.z.floor();
+        if is_even(sum) {
+            pattern_at(self.a.as_ref(), pattern_point)
+        } else {
+            pattern_at(self.b.as_ref(), pattern_point)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Blend {
+    transform: Transform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+impl Blend {
+    pub fn new(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        Blend {
+            transform: Transform::default(),
+            a,
+            b,
+        }
+    }
+}
+impl Pattern for Blend {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        (pattern_at(self.a.as_ref(), pattern_point) + pattern_at(self.b.as_ref(), pattern_point))
+            / 2.
+    }
+}
+
+#[derive(Debug)]
+pub struct Perturbed {
+    transform: Transform,
+    pub pattern: Box<dyn Pattern>,
+    pub scale: f64,
+}
+impl Perturbed {
+    pub fn new(pattern: Box<dyn Pattern>, scale: f64) -> Self {
+        Perturbed {
+            transform: Transform::default(),
+            pattern,
+            scale,
+        }
+    }
+}
+impl Pattern for Perturbed {
+    fn transform_data(&self) -> &Transform {
+        &self.transform
+    }
+    fn transform_data_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn local_pattern_at(&self, pattern_point: Tuple) -> Color {
+        let (x, y, z) = (pattern_point.x, pattern_point.y, pattern_point.z);
+        let jittered = Tuple::point(
+            x + noise(x, y, z) * self.scale,
+            y + noise(x, y, z + 1.) * self.scale,
+            z + noise(x, y, z + 2.) * self.scale,
+        );
+        pattern_at(self.pattern.as_ref(), jittered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::Color, matrix::Matrix, tuple::Tuple};
+
+    use super::{pattern_at, Blend, Checker, Gradient, Pattern, Perturbed, Ring, Solid, Stripe};
+
+    fn white() -> Color {
+        Color::new(1., 1., 1.)
+    }
+    fn black() -> Color {
+        Color::new(0., 0., 0.)
+    }
+
+    #[test]
+    fn test_new_stripe() {
+        let p = Stripe::new(white(), black());
+        assert_eq!(p.a.local_pattern_at(Tuple::point(0., 0., 0.)), white());
+        assert_eq!(p.b.local_pattern_at(Tuple::point(0., 0., 0.)), black());
+        assert_eq!(*p.transform(), Matrix::identity());
+    }
+
+    #[test]
+    fn test_stripe_constant_in_y_and_z() {
+        let p = Stripe::new(white(), black());
+        for (y, z) in [(0., 0.), (1., 0.), (2., 0.), (0., 1.), (0., 2.)] {
+            assert_eq!(p.local_pattern_at(Tuple::point(0., y, z)), white());
+        }
+    }
+
+    #[test]
+    fn test_stripe_alternates_in_x() {
+        let p = Stripe::new(white(), black());
+        let cases = [
+            (0., white()),
+            (0.9, white()),
+            (1., black()),
+            (-0.1, black()),
+            (-1., black()),
+            (-1.1, white()),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(p.local_pattern_at(Tuple::point(x, 0., 0.)), expected);
+        }
+    }
+
+    #[test]
+    fn test_pattern_transform() {
+        let mut p = Stripe::new(white(), black());
+        p.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        assert_eq!(pattern_at(&p, Tuple::point(1.5, 0., 0.)), white());
+
+        p.set_transform(Matrix::translation(0.5, 0., 0.))
+            .expect("Transform should be invertible");
+        assert_eq!(pattern_at(&p, Tuple::point(2.5, 0., 0.)), white());
+    }
+
+    #[test]
+    fn test_gradient_interpolates() {
+        let p = Gradient::new(white(), black());
+        let cases = [
+            (0., white()),
+            (0.25, Color::new(0.75, 0.75, 0.75)),
+            (0.5, Color::new(0.5, 0.5, 0.5)),
+            (0.75, Color::new(0.25, 0.25, 0.25)),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(p.local_pattern_at(Tuple::point(x, 0., 0.)), expected);
+        }
+    }
+
+    #[test]
+    fn test_ring_extends_in_x_and_z() {
+        let p = Ring::new(white(), black());
+        assert_eq!(p.local_pattern_at(Tuple::point(0., 0., 0.)), white());
+        assert_eq!(p.local_pattern_at(Tuple::point(1., 0., 0.)), black());
+        assert_eq!(p.local_pattern_at(Tuple::point(0., 0., 1.)), black());
+        assert_eq!(p.local_pattern_at(Tuple::point(0.708, 0., 0.708)), black());
+    }
+
+    #[test]
+    fn test_checker_repeats_in_each_dimension() {
+        let p = Checker::new(white(), black());
+        let cases = [
+            (Tuple::point(0., 0., 0.), white()),
+            (Tuple::point(0.99, 0., 0.), white()),
+            (Tuple::point(1.01, 0., 0.), black()),
+            (Tuple::point(0., 0.99, 0.), white()),
+            (Tuple::point(0., 1.01, 0.), black()),
+            (Tuple::point(0., 0., 0.99), white()),
+            (Tuple::point(0., 0., 1.01), black()),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(p.local_pattern_at(point), expected);
+        }
+    }
+
+    #[test]
+    fn test_nested_patterns() {
+        let mut inner = Stripe::new(Color::new(1., 0., 0.), Color::new(0., 0., 1.));
+        inner
+            .set_transform(Matrix::scale(0.25, 0.25, 0.25))
+            .expect("Transform should be invertible");
+        let p = Checker::nested(Box::new(inner), Box::new(Solid::new(black())));
+        assert_eq!(
+            p.local_pattern_at(Tuple::point(0.1, 0.5, 0.5)),
+            Color::new(1., 0., 0.)
+        );
+        assert_eq!(
+            p.local_pattern_at(Tuple::point(0.3, 0.5, 0.5)),
+            Color::new(0., 0., 1.)
+        );
+        assert_eq!(p.local_pattern_at(Tuple::point(1.1, 0.5, 0.5)), black());
+    }
+
+    #[test]
+    fn test_blend_averages_patterns() {
+        let a = Stripe::new(white(), black());
+        let mut b = Stripe::new(white(), black());
+        b.set_transform(Matrix::rot_y(std::f64::consts::PI / 2.))
+            .expect("Transform should be invertible");
+        let p = Blend::new(Box::new(a), Box::new(b));
+        assert_eq!(p.local_pattern_at(Tuple::point(0.5, 0., -0.5)), white());
+        assert_eq!(
+            p.local_pattern_at(Tuple::point(1.5, 0., -0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_perturbed_pattern_jitters_lookup() {
+        let p = Perturbed::new(Box::new(Stripe::new(white(), black())), 0.);
+        assert_eq!(p.local_pattern_at(Tuple::point(0.9, 0.3, 0.2)), white());
+
+        let p = Perturbed::new(Box::new(Gradient::new(white(), black())), 0.5);
+        let point = Tuple::point(0.3, 0.4, 0.7);
+        let plain = Gradient::new(white(), black()).local_pattern_at(point);
+        assert_ne!(p.local_pattern_at(point), plain);
+        assert_eq!(p.local_pattern_at(point), p.local_pattern_at(point));
+        assert_eq!(p.local_pattern_at(Tuple::point(0., 1., 2.)), white());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::{Intersection, EPSILON},
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plane {
+    data: ShapeData,
+}
+impl Plane {
+    pub fn new() -> Self {
+        Plane {
+            data: ShapeData::new(),
+        }
+    }
+    pub fn with_mat(material: Material) -> Self {
+        Plane {
+            data: ShapeData::with_mat(material),
+        }
+    }
+}
+impl Default for Plane {
+    fn default() -> Self {
+        Plane::new()
+    }
+}
+impl Shape for Plane {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        if ray.direction.y.abs() < EPSILON {
+            Intersections::new()
+        } else {
+            Intersections::from([Intersection::new(-ray.origin.y / ray.direction.y, self)])
+        }
+    }
+
+    fn local_normal_at(&self, _object_point: Tuple, _hit: &Intersection) -> Tuple {
+        Tuple::vector(0., 1., 0.)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, 0., f64::INFINITY),
+        )
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersect::intersect,
+        intersection::Intersection,
+        matrix::Matrix,
+        object::{normal_at, Shape},
+        ray::Ray,
+        tuple::Tuple,
+    };
+
+    use super::Plane;
+
+    #[test]
+    fn test_normal_constant() {
+        let p = Plane::new();
+        let expected = Tuple::vector(0., 1., 0.);
+        assert_eq!(
+            p.local_normal_at(Tuple::point(0., 0., 0.), &Intersection::new(0., &p)),
+            expected
+        );
+        assert_eq!(
+            p.local_normal_at(Tuple::point(10., 0., -10.), &Intersection::new(0., &p)),
+            expected
+        );
+        assert_eq!(
+            p.local_normal_at(Tuple::point(-5., 0., 150.), &Intersection::new(0., &p)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_normal_transformed() {
+        let mut p = Plane::new();
+        p.set_transform(Matrix::scale(1., 1., -1.) * Matrix::translation(0., 2., 0.))
+            .expect("Transform should be invertible");
+        assert_eq!(
+            normal_at(&p, Tuple::point(3., 2., 1.), &Intersection::new(0., &p)),
+            Tuple::vector(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 10., 0.), Tuple::vector(0., 0., 1.));
+        assert_eq!(p.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_intersect_coplanar() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        assert_eq!(p.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_intersect_from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        let mut xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        let i = xs.pop().expect("Should have intersection");
+        assert_eq!(i.t, 1.);
+        assert_eq!(i.o, &p as &dyn Shape);
+    }
+
+    #[test]
+    fn test_intersect_from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., -1., 0.), Tuple::vector(0., 1., 0.));
+        let mut xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        let i = xs.pop().expect("Should have intersection");
+        assert_eq!(i.t, 1.);
+        assert_eq!(i.o, &p as &dyn Shape);
+    }
+
+    #[test]
+    fn test_intersect_translated() {
+        let mut p = Plane::new();
+        p.set_transform(Matrix::translation(0., -1., 0.))
+            .expect("Transform should be invertible");
+        let r = Ray::new(
+            Tuple::point(0., 1., -5.),
+            Tuple::vector(0., -1., 1.).normalize(),
+        );
+        let mut xs = intersect(&r, &p);
+        assert_eq!(xs.len(), 1);
+        let i = xs.pop().expect("Should have intersection");
+        assert!((i.t - 2. * f64::sqrt(2.)).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,366 @@
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Stored,
+    Fixed,
+}
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const MAX_STORED_BLOCK: usize = 65535;
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_SIZE: usize = 1 << 15;
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: vec![],
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.bit_buffer |= value << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+    fn write_code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.write_bits(reversed, length);
+    }
+    fn align(&mut self) {
+        if self.bit_count > 0 {
+            self.write_bits(0, 8 - self.bit_count);
+        }
+    }
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+fn write_literal(out: &mut BitWriter, symbol: usize) {
+    match symbol {
+        0..=143 => out.write_code(0x30 + symbol as u32, 8),
+        144..=255 => out.write_code(0x190 + (symbol - 144) as u32, 9),
+        256..=279 => out.write_code((symbol - 256) as u32, 7),
+        _ => out.write_code(0xc0 + (symbol - 280) as u32, 8),
+    }
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base <= length)
+        .unwrap_or(0);
+    write_literal(out, 257 + code);
+    out.write_bits((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code]);
+    let code = DIST_BASE
+        .iter()
+        .rposition(|&base| base <= distance)
+        .unwrap_or(0);
+    out.write_code(code as u32, 5);
+    out.write_bits((distance - DIST_BASE[code]) as u32, DIST_EXTRA[code]);
+}
+
+fn hash(data: &[u8], pos: usize) -> usize {
+    let v = (data[pos] as usize) << 16 | (data[pos + 1] as usize) << 8 | data[pos + 2] as usize;
+    (v.wrapping_mul(2654435761) >> 7) & (HASH_SIZE - 1)
+}
+
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out
+}
+
+// A single fixed-Huffman block, with greedy LZ77 matching against the most
+// recent position sharing the same 3-byte hash.
+fn deflate_fixed(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::new();
+    out.write_bits(1, 1);
+    out.write_bits(1, 2);
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = 0;
+        let mut distance = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(data, pos);
+            let candidate = head[h];
+            head[h] = pos;
+            if candidate != usize::MAX && pos - candidate <= WINDOW_SIZE {
+                let limit = MAX_MATCH.min(data.len() - pos);
+                while best < limit && data[candidate + best] == data[pos + best] {
+                    best += 1;
+                }
+                distance = pos - candidate;
+            }
+        }
+        if best >= MIN_MATCH {
+            write_match(&mut out, best, distance);
+            for p in pos + 1..(pos + best).min(data.len().saturating_sub(MIN_MATCH - 1)) {
+                head[hash(data, p)] = p;
+            }
+            pos += best;
+        } else {
+            write_literal(&mut out, data[pos] as usize);
+            pos += 1;
+        }
+    }
+    write_literal(&mut out, 256);
+    out.finish()
+}
+
+pub fn zlib_compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    out.extend(match compression {
+        Compression::Stored => deflate_stored(data),
+        Compression::Fixed => deflate_fixed(data),
+    });
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn write_chunk<T: Write>(writeable: &mut T, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writeable.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut crc_input = kind.to_vec();
+    crc_input.extend(data);
+    writeable.write_all(&crc_input)?;
+    writeable.write_all(&crc32(&crc_input).to_be_bytes())
+}
+
+pub fn write_png<T: Write>(
+    writeable: &mut T,
+    width: usize,
+    height: usize,
+    depth: BitDepth,
+    compression: Compression,
+    scanlines: &[u8],
+) -> io::Result<()> {
+    writeable.write_all(&SIGNATURE)?;
+    let mut ihdr = vec![];
+    ihdr.extend((width as u32).to_be_bytes());
+    ihdr.extend((height as u32).to_be_bytes());
+    ihdr.push(match depth {
+        BitDepth::Eight => 8,
+        BitDepth::Sixteen => 16,
+    });
+    ihdr.extend([2, 0, 0, 0]);
+    write_chunk(writeable, b"IHDR", &ihdr)?;
+    write_chunk(writeable, b"IDAT", &zlib_compress(scanlines, compression))?;
+    write_chunk(writeable, b"IEND", &[])?;
+    writeable.flush()
+}
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, zlib_compress, Compression};
+
+    struct BitReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+        bit: u32,
+    }
+    impl BitReader<'_> {
+        fn bits(&mut self, count: u32) -> usize {
+            let mut value = 0;
+            for i in 0..count {
+                let b = (self.data[self.pos] >> self.bit) & 1;
+                value |= (b as usize) << i;
+                self.bit += 1;
+                if self.bit == 8 {
+                    self.bit = 0;
+                    self.pos += 1;
+                }
+            }
+            value
+        }
+        fn code(&mut self, count: u32) -> usize {
+            (0..count).fold(0, |acc, _| (acc << 1) | self.bits(1))
+        }
+        fn fixed_symbol(&mut self) -> usize {
+            let code = self.code(7);
+            if code <= 0x17 {
+                return code + 256;
+            }
+            let code = (code << 1) | self.bits(1);
+            match code {
+                0x30..=0xbf => code - 0x30,
+                0xc0..=0xc7 => code - 0xc0 + 280,
+                _ => ((code << 1) | self.bits(1)) - 0x190 + 144,
+            }
+        }
+    }
+
+    // Minimal inflate for the two block types the encoder produces.
+    fn inflate(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!((zlib[0] as u32 * 256 + zlib[1] as u32) % 31, 0);
+        let mut r = BitReader {
+            data: &zlib[2..],
+            pos: 0,
+            bit: 0,
+        };
+        let mut out: Vec<u8> = vec![];
+        loop {
+            let last = r.bits(1);
+            match r.bits(2) {
+                0 => {
+                    if r.bit > 0 {
+                        r.bit = 0;
+                        r.pos += 1;
+                    }
+                    let len = r.data[r.pos] as usize | (r.data[r.pos + 1] as usize) << 8;
+                    let nlen = r.data[r.pos + 2] as usize | (r.data[r.pos + 3] as usize) << 8;
+                    assert_eq!(len ^ 0xffff, nlen);
+                    out.extend(&r.data[r.pos + 4..r.pos + 4 + len]);
+                    r.pos += 4 + len;
+                }
+                1 => loop {
+                    let symbol = r.fixed_symbol();
+                    if symbol < 256 {
+                        out.push(symbol as u8);
+                    } else if symbol == 256 {
+                        break;
+                    } else {
+                        let code = symbol - 257;
+                        let length = super::LENGTH_BASE[code] + r.bits(super::LENGTH_EXTRA[code]);
+                        let code = r.code(5);
+                        let distance = super::DIST_BASE[code] + r.bits(super::DIST_EXTRA[code]);
+                        for _ in 0..length {
+                            out.push(out[out.len() - distance]);
+                        }
+                    }
+                },
+                t => panic!("unexpected block type {t}"),
+            }
+            if last == 1 {
+                break;
+            }
+        }
+        if r.bit > 0 {
+            r.pos += 1;
+        }
+        let checksum = u32::from_be_bytes(r.data[r.pos..r.pos + 4].try_into().expect("4 bytes"));
+        assert_eq!(checksum, adler32(&out));
+        out
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&vec![0xff; 100_000]), 0x149a_302c);
+    }
+
+    #[test]
+    fn test_zlib_round_trip() {
+        let mut noisy = vec![];
+        let mut seed = 12345u32;
+        for _ in 0..70_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            noisy.push((seed >> 16) as u8);
+        }
+        let inputs: Vec<Vec<u8>> = vec![
+            vec![],
+            b"a".to_vec(),
+            b"abcabcabcabcabcabcabcabcabc hello hello hello".to_vec(),
+            vec![7; 100_000],
+            (0..200_000).map(|i| (i % 251) as u8).collect(),
+            noisy,
+        ];
+        for input in inputs {
+            for compression in [Compression::Stored, Compression::Fixed] {
+                let compressed = zlib_compress(&input, compression);
+                assert_eq!(inflate(&compressed), input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_compresses_repetitive_data() {
+        let input = vec![42; 100_000];
+        let stored = zlib_compress(&input, Compression::Stored);
+        let fixed = zlib_compress(&input, Compression::Fixed);
+        assert!(stored.len() > input.len());
+        assert!(fixed.len() < input.len() / 50);
+    }
+}
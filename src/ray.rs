@@ -24,7 +24,8 @@ impl Ray {
 mod tests {
     use crate::intersect::intersect;
     use crate::matrix::Matrix;
-    use crate::object::{transform, Object};
+    use crate::object::Shape;
+    use crate::sphere::Sphere;
     use crate::tuple::Tuple;
     use crate::{ray, tuple};
     #[test]
@@ -76,8 +77,9 @@ mod tests {
     #[test]
     fn test_ray_intersect_scale() {
         let r = ray::Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
-        let s = Object::sphere();
-        let s = transform(&s, &Matrix::scale(2., 2., 2.));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
         let mut xs = intersect(&r, &s);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs.pop().expect("").t, 3.);
@@ -86,8 +88,9 @@ mod tests {
     #[test]
     fn test_ray_intersect_translate() {
         let r = ray::Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
-        let s = Object::sphere();
-        let s = transform(&s, &Matrix::translation(5., 0., 0.));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5., 0., 0.))
+            .expect("Transform should be invertible");
         let xs = intersect(&r, &s);
         assert_eq!(xs.len(), 0);
     }
@@ -0,0 +1,1279 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    cone::Cone,
+    csg::{Csg, CsgOp},
+    cube::Cube,
+    cylinder::Cylinder,
+    group::Group,
+    material::Material,
+    matrix::Matrix,
+    obj_file::parse_obj_file,
+    object::Shape,
+    pattern::{Blend, Checker, Gradient, Pattern, Perturbed, Ring, Solid, Stripe},
+    plane::Plane,
+    point_light::Point_Light,
+    sphere::Sphere,
+    triangle::Triangle,
+    tuple::Tuple,
+    world::World,
+};
+
+const MAX_DEFINE_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub struct SceneError {
+    pub line: usize,
+    pub message: String,
+}
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl std::error::Error for SceneError {}
+
+fn error<T>(line: usize, message: String) -> Result<T, SceneError> {
+    Err(SceneError { line, message })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+impl Node {
+    fn describe(&self) -> String {
+        match &self.value {
+            Value::Scalar(s) => format!("'{s}'"),
+            Value::List(_) => "a list".to_string(),
+            Value::Map(_) => "a map".to_string(),
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, SceneError> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => error(
+                self.line,
+                format!("expected a value, found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_list(&self) -> Result<&[Node], SceneError> {
+        match &self.value {
+            Value::List(items) => Ok(items),
+            _ => error(
+                self.line,
+                format!("expected a list, found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_map(&self) -> Result<&[(String, Node)], SceneError> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => error(
+                self.line,
+                format!("expected a map, found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64, SceneError> {
+        let s = self.as_str()?;
+        match s.parse::<f64>() {
+            Ok(v) if !v.is_nan() => Ok(v),
+            _ => error(self.line, format!("expected a number, found '{s}'")),
+        }
+    }
+
+    fn as_usize(&self) -> Result<usize, SceneError> {
+        let s = self.as_str()?;
+        match s.parse::<usize>() {
+            Ok(v) if v > 0 => Ok(v),
+            _ => error(
+                self.line,
+                format!("expected a positive integer, found '{s}'"),
+            ),
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool, SceneError> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            s => error(self.line, format!("expected true or false, found '{s}'")),
+        }
+    }
+
+    fn as_triple(&self) -> Result<[f64; 3], SceneError> {
+        let items = self.as_list()?;
+        if items.len() != 3 {
+            return error(
+                self.line,
+                format!("expected 3 numbers, found {}", items.len()),
+            );
+        }
+        Ok([items[0].as_f64()?, items[1].as_f64()?, items[2].as_f64()?])
+    }
+
+    fn as_color(&self) -> Result<Color, SceneError> {
+        let [r, g, b] = self.as_triple()?;
+        Ok(Color::new(r, g, b))
+    }
+}
+
+fn get<'n>(entries: &'n [(String, Node)], key: &str) -> Option<&'n Node> {
+    entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn require<'n>(
+    node: &Node,
+    entries: &'n [(String, Node)],
+    key: &str,
+) -> Result<&'n Node, SceneError> {
+    match get(entries, key) {
+        Some(value) => Ok(value),
+        None => error(node.line, format!("missing '{key}'")),
+    }
+}
+
+fn check_keys(entries: &[(String, Node)], allowed: &[&str], what: &str) -> Result<(), SceneError> {
+    for (key, value) in entries {
+        if !allowed.contains(&key.as_str()) {
+            return error(value.line, format!("unknown key '{key}' for {what}"));
+        }
+    }
+    Ok(())
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '{', '"', '\'']) {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    text.char_indices()
+        .find(|&(i, c)| c == ':' && (i + 1 == text.len() || bytes[i + 1] == b' '))
+        .map(|(i, _)| (text[..i].trim_end(), text[i + 1..].trim()))
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &text[..i],
+            None => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+struct Flow<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+impl Flow<'_> {
+    fn skip_spaces(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn expect_separator(&mut self, close: char) -> Result<bool, SceneError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => error(self.line, format!("expected ',' or '{close}'")),
+        }
+    }
+
+    fn plain(&mut self, stop: &[char]) -> &str {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| !stop.contains(c)) {
+            self.pos += c.len_utf8();
+        }
+        self.text[start..self.pos].trim()
+    }
+
+    fn value(&mut self, nested: bool) -> Result<Node, SceneError> {
+        self.skip_spaces();
+        let value = match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip_spaces();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        items.push(self.value(true)?);
+                        if self.expect_separator(']')? {
+                            break;
+                        }
+                    }
+                }
+                Value::List(items)
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = vec![];
+                self.skip_spaces();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        let key = self.plain(&[':', ',', '}']).to_string();
+                        if self.peek() != Some(':') || key.is_empty() {
+                            return error(self.line, "expected 'key: value' in map".to_string());
+                        }
+                        self.pos += 1;
+                        entries.push((key, self.value(true)?));
+                        if self.expect_separator('}')? {
+                            break;
+                        }
+                    }
+                }
+                Value::Map(entries)
+            }
+            Some(q @ ('"' | '\'')) => {
+                self.pos += 1;
+                let Some(len) = self.text[self.pos..].find(q) else {
+                    return error(self.line, "unterminated string".to_string());
+                };
+                let s = self.text[self.pos..self.pos + len].to_string();
+                self.pos += len + 1;
+                Value::Scalar(s)
+            }
+            _ => {
+                let stop: &[char] = if nested { &[',', ']', '}'] } else { &[] };
+                let s = self.plain(stop);
+                if s.is_empty() {
+                    return error(self.line, "missing value".to_string());
+                }
+                Value::Scalar(s.to_string())
+            }
+        };
+        Ok(Node {
+            line: self.line,
+            value,
+        })
+    }
+}
+
+fn parse_flow_value(text: &str, line: usize) -> Result<Node, SceneError> {
+    let mut flow = Flow { text, pos: 0, line };
+    let node = flow.value(false)?;
+    flow.skip_spaces();
+    if flow.pos < text.len() {
+        return error(line, format!("unexpected '{}'", &text[flow.pos..]));
+    }
+    Ok(node)
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+impl Parser {
+    fn parse_block(&mut self, indent: usize) -> Result<Node, SceneError> {
+        if is_sequence_item(&self.lines[self.pos].text) {
+            self.parse_sequence(indent)
+        } else {
+            self.parse_map(indent)
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let start = self.lines[self.pos].number;
+        let mut items = vec![];
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent || !is_sequence_item(&line.text) {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation".to_string());
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                self.pos += 1;
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let child_indent = next.indent;
+                        items.push(self.parse_block(child_indent)?);
+                    }
+                    _ => return error(number, "empty list item".to_string()),
+                }
+            } else if split_key(&rest).is_some() || is_sequence_item(&rest) {
+                // The item starts a block on the same line as its dash, so
+                // re-read the rest of the line as if it were indented.
+                let child_indent = indent + line.text.len() - rest.len();
+                let line = &mut self.lines[self.pos];
+                line.indent = child_indent;
+                line.text = rest;
+                items.push(self.parse_block(child_indent)?);
+            } else {
+                items.push(parse_flow_value(&rest, number)?);
+                self.pos += 1;
+            }
+        }
+        Ok(Node {
+            line: start,
+            value: Value::List(items),
+        })
+    }
+
+    fn parse_map(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let start = self.lines[self.pos].number;
+        let mut entries: Vec<(String, Node)> = vec![];
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation".to_string());
+            }
+            let number = line.number;
+            let Some((key, rest)) = split_key(&line.text) else {
+                return error(
+                    number,
+                    format!("expected 'key: value', found '{}'", line.text),
+                );
+            };
+            let (key, rest) = (key.to_string(), rest.to_string());
+            if get(&entries, &key).is_some() {
+                return error(number, format!("duplicate key '{key}'"));
+            }
+            self.pos += 1;
+            let value = if !rest.is_empty() {
+                parse_flow_value(&rest, number)?
+            } else {
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let child_indent = next.indent;
+                        self.parse_block(child_indent)?
+                    }
+                    Some(next) if next.indent == indent && is_sequence_item(&next.text) => {
+                        self.parse_sequence(indent)?
+                    }
+                    _ => return error(number, format!("missing value for '{key}'")),
+                }
+            };
+            entries.push((key, value));
+        }
+        Ok(Node {
+            line: start,
+            value: Value::Map(entries),
+        })
+    }
+}
+
+fn parse_document(input: &str) -> Result<Node, SceneError> {
+    let mut lines = vec![];
+    for (pos, text) in input.lines().enumerate() {
+        let text = strip_comment(text).trim_end();
+        let content = text.trim_start_matches(' ');
+        if content.is_empty() || text == "---" {
+            continue;
+        }
+        if content.starts_with('\t') {
+            return error(pos + 1, "tabs are not allowed in indentation".to_string());
+        }
+        lines.push(Line {
+            number: pos + 1,
+            indent: text.len() - content.len(),
+            text: content.to_string(),
+        });
+    }
+    if lines.is_empty() {
+        return Ok(Node {
+            line: 1,
+            value: Value::List(vec![]),
+        });
+    }
+    let mut parser = Parser { lines, pos: 0 };
+    let indent = parser.lines[0].indent;
+    let node = parser.parse_block(indent)?;
+    if let Some(line) = parser.lines.get(parser.pos) {
+        return error(line.number, format!("unexpected '{}'", line.text));
+    }
+    Ok(node)
+}
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+struct Builder<'a> {
+    base_dir: &'a Path,
+    defines: HashMap<String, Node>,
+    camera: Option<Camera>,
+    world: World,
+}
+impl Builder<'_> {
+    fn define(&self, name: &Node) -> Result<&Node, SceneError> {
+        let name_str = name.as_str()?;
+        match self.defines.get(name_str) {
+            Some(value) => Ok(value),
+            None => error(name.line, format!("unknown define '{name_str}'")),
+        }
+    }
+
+    fn add_define(&mut self, item: &Node, entries: &[(String, Node)]) -> Result<(), SceneError> {
+        check_keys(entries, &["define", "extend", "value"], "define")?;
+        let name = require(item, entries, "define")?.as_str()?.to_string();
+        let value = require(item, entries, "value")?;
+        let value = match get(entries, "extend") {
+            None => value.clone(),
+            Some(base) => {
+                let base = self.define(base)?;
+                let (Value::Map(base_entries), Value::Map(own)) = (&base.value, &value.value)
+                else {
+                    return error(value.line, "'extend' requires map values".to_string());
+                };
+                let mut merged: Vec<(String, Node)> = base_entries
+                    .iter()
+                    .filter(|(k, _)| get(own, k).is_none())
+                    .cloned()
+                    .collect();
+                merged.extend(own.iter().cloned());
+                Node {
+                    line: value.line,
+                    value: Value::Map(merged),
+                }
+            }
+        };
+        self.defines.insert(name, value);
+        Ok(())
+    }
+
+    fn add_camera(&mut self, item: &Node, entries: &[(String, Node)]) -> Result<(), SceneError> {
+        check_keys(
+            entries,
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+            ],
+            "camera",
+        )?;
+        if self.camera.is_some() {
+            return error(item.line, "scene has more than one camera".to_string());
+        }
+        let width = require(item, entries, "width")?.as_usize()?;
+        let height = require(item, entries, "height")?.as_usize()?;
+        let fov = require(item, entries, "field-of-view")?.as_f64()?;
+        let [fx, fy, fz] = require(item, entries, "from")?.as_triple()?;
+        let [tx, ty, tz] = require(item, entries, "to")?.as_triple()?;
+        let [ux, uy, uz] = require(item, entries, "up")?.as_triple()?;
+        let view = Matrix::view_transform(
+            Tuple::point(fx, fy, fz),
+            Tuple::point(tx, ty, tz),
+            Tuple::vector(ux, uy, uz),
+        );
+        let mut camera = Camera::new(width, height, fov);
+        camera
+            .set_transform(view)
+            .or_else(|e| error(item.line, format!("camera view {e}")))?;
+        self.camera = Some(camera);
+        Ok(())
+    }
+
+    fn add_light(&mut self, item: &Node, entries: &[(String, Node)]) -> Result<(), SceneError> {
+        check_keys(entries, &["add", "at", "intensity"], "light")?;
+        let [x, y, z] = require(item, entries, "at")?.as_triple()?;
+        let intensity = require(item, entries, "intensity")?.as_color()?;
+        self.world
+            .lights
+            .push(Point_Light::new(intensity, Tuple::point(x, y, z)));
+        Ok(())
+    }
+
+    fn transform(&self, node: &Node, depth: usize) -> Result<Matrix, SceneError> {
+        if depth > MAX_DEFINE_DEPTH {
+            return error(node.line, "defines nested too deeply".to_string());
+        }
+        if let Value::Scalar(_) = node.value {
+            return self.transform(self.define(node)?, depth + 1);
+        }
+        let mut m = Matrix::identity();
+        for item in node.as_list()? {
+            if let Value::Scalar(_) = item.value {
+                m = self.transform(self.define(item)?, depth + 1)? * m;
+                continue;
+            }
+            let parts = item.as_list()?;
+            let Some((op, args)) = parts.split_first() else {
+                return error(item.line, "empty transform".to_string());
+            };
+            let op = op.as_str()?;
+            let args = args
+                .iter()
+                .map(|a| a.as_f64())
+                .collect::<Result<Vec<f64>, SceneError>>()?;
+            let expected = match op {
+                "translate" | "scale" => 3,
+                "rotate-x" | "rotate-y" | "rotate-z" => 1,
+                "shear" => 6,
+                _ => return error(item.line, format!("unknown transform '{op}'")),
+            };
+            if args.len() != expected {
+                return error(
+                    item.line,
+                    format!("'{op}' needs {expected} values, found {}", args.len()),
+                );
+            }
+            let t = match op {
+                "translate" => Matrix::translation(args[0], args[1], args[2]),
+                "scale" => Matrix::scale(args[0], args[1], args[2]),
+                "rotate-x" => Matrix::rot_x(args[0]),
+                "rotate-y" => Matrix::rot_y(args[0]),
+                "rotate-z" => Matrix::rot_z(args[0]),
+                _ => Matrix::shearing(args[0], args[1], args[2], args[3], args[4], args[5]),
+            };
+            m = t * m;
+        }
+        Ok(m)
+    }
+
+    fn pattern_pair(
+        &self,
+        node: &Node,
+        entries: &[(String, Node)],
+        depth: usize,
+    ) -> Result<[Box<dyn Pattern>; 2], SceneError> {
+        let pair = |key: &str| -> Result<Option<[&Node; 2]>, SceneError> {
+            let Some(value) = get(entries, key) else {
+                return Ok(None);
+            };
+            match value.as_list()? {
+                [a, b] => Ok(Some([a, b])),
+                items => error(
+                    value.line,
+                    format!("'{key}' needs 2 entries, found {}", items.len()),
+                ),
+            }
+        };
+        if let Some([a, b]) = pair("colors")? {
+            Ok([
+                Box::new(Solid::new(a.as_color()?)),
+                Box::new(Solid::new(b.as_color()?)),
+            ])
+        } else if let Some([a, b]) = pair("patterns")? {
+            Ok([self.pattern(a, depth + 1)?, self.pattern(b, depth + 1)?])
+        } else {
+            error(
+                node.line,
+                "pattern needs 'colors' or 'patterns'".to_string(),
+            )
+        }
+    }
+
+    fn pattern(&self, node: &Node, depth: usize) -> Result<Box<dyn Pattern>, SceneError> {
+        if depth > MAX_DEFINE_DEPTH {
+            return error(node.line, "defines nested too deeply".to_string());
+        }
+        let node = match node.value {
+            Value::Scalar(_) => self.define(node)?,
+            _ => node,
+        };
+        let entries = node.as_map()?;
+        check_keys(
+            entries,
+            &[
+                "type",
+                "color",
+                "colors",
+                "patterns",
+                "pattern",
+                "scale",
+                "transform",
+            ],
+            "pattern",
+        )?;
+        let kind = require(node, entries, "type")?;
+        let mut pattern: Box<dyn Pattern> = match kind.as_str()? {
+            "solid" => Box::new(Solid::new(require(node, entries, "color")?.as_color()?)),
+            "stripes" => {
+                let [a, b] = self.pattern_pair(node, entries, depth)?;
+                Box::new(Stripe::nested(a, b))
+            }
+            "gradient" => {
+                let [a, b] = self.pattern_pair(node, entries, depth)?;
+                Box::new(Gradient::nested(a, b))
+            }
+            "rings" => {
+                let [a, b] = self.pattern_pair(node, entries, depth)?;
+                Box::new(Ring::nested(a, b))
+            }
+            "checkers" => {
+                let [a, b] = self.pattern_pair(node, entries, depth)?;
+                Box::new(Checker::nested(a, b))
+            }
+            "blend" => {
+                let [a, b] = self.pattern_pair(node, entries, depth)?;
+                Box::new(Blend::new(a, b))
+            }
+            "perturbed" => Box::new(Perturbed::new(
+                self.pattern(require(node, entries, "pattern")?, depth + 1)?,
+                require(node, entries, "scale")?.as_f64()?,
+            )),
+            other => return error(kind.line, format!("unknown pattern type '{other}'")),
+        };
+        if let Some(transform) = get(entries, "transform") {
+            let m = self.transform(transform, 0)?;
+            pattern
+                .set_transform(m)
+                .or_else(|e| error(transform.line, format!("pattern {e}")))?;
+        }
+        Ok(pattern)
+    }
+
+    fn material(&self, node: &Node, inherited: &Material) -> Result<Material, SceneError> {
+        let node = match node.value {
+            Value::Scalar(_) => self.define(node)?,
+            _ => node,
+        };
+        let mut mat = inherited.clone();
+        for (key, value) in node.as_map()? {
+            match key.as_str() {
+                "color" => mat.color = value.as_color()?,
+                "ambient" => mat.ambiant = value.as_f64()?,
+                "diffuse" => mat.diffuse = value.as_f64()?,
+                "specular" => mat.specular = value.as_f64()?,
+                "shininess" => mat.shininess = value.as_f64()?,
+                "reflective" => mat.reflective = value.as_f64()?,
+                "transparency" => mat.transparency = value.as_f64()?,
+                "refractive-index" => mat.refractive_index = value.as_f64()?,
+                "pattern" => mat.pattern = Some(Arc::from(self.pattern(value, 0)?)),
+                _ => return error(value.line, format!("unknown key '{key}' for material")),
+            }
+        }
+        Ok(mat)
+    }
+
+    fn shape(
+        &self,
+        node: &Node,
+        inherited: &Material,
+        depth: usize,
+    ) -> Result<Box<dyn Shape>, SceneError> {
+        let entries = node.as_map()?;
+        let kind = require(node, entries, "add")?;
+        let kind_str = kind.as_str()?;
+        if let Some(define) = self.defines.get(kind_str) {
+            if depth > MAX_DEFINE_DEPTH {
+                return error(kind.line, "defines nested too deeply".to_string());
+            }
+            // The item's own keys override the defined object's keys.
+            let mut merged: Vec<(String, Node)> = define
+                .as_map()?
+                .iter()
+                .filter(|(k, _)| k == "add" || get(entries, k).is_none())
+                .cloned()
+                .collect();
+            merged.extend(entries.iter().filter(|(k, _)| k != "add").cloned());
+            let merged = Node {
+                line: node.line,
+                value: Value::Map(merged),
+            };
+            return self.shape(&merged, inherited, depth + 1);
+        }
+
+        let common = ["add", "material", "transform"];
+        let keys = |extra: &[&str]| {
+            let allowed: Vec<&str> = common.iter().chain(extra).copied().collect();
+            check_keys(entries, &allowed, kind_str)
+        };
+        let material = match get(entries, "material") {
+            Some(m) => self.material(m, inherited)?,
+            None => inherited.clone(),
+        };
+        let number = |key: &str, default: f64| match get(entries, key) {
+            Some(v) => v.as_f64(),
+            None => Ok(default),
+        };
+        let closed = || match get(entries, "closed") {
+            Some(v) => v.as_bool(),
+            None => Ok(false),
+        };
+        let point = |key: &str| -> Result<Tuple, SceneError> {
+            let [x, y, z] = require(node, entries, key)?.as_triple()?;
+            Ok(Tuple::point(x, y, z))
+        };
+
+        let mut shape: Box<dyn Shape> = match kind_str {
+            "sphere" => {
+                keys(&[])?;
+                Box::new(Sphere::with_mat(material))
+            }
+            "plane" => {
+                keys(&[])?;
+                Box::new(Plane::with_mat(material))
+            }
+            "cube" => {
+                keys(&[])?;
+                Box::new(Cube::with_mat(material))
+            }
+            "cylinder" => {
+                keys(&["min", "max", "closed"])?;
+                Box::new(Cylinder::truncated_with_mat(
+                    number("min", f64::NEG_INFINITY)?,
+                    number("max", f64::INFINITY)?,
+                    closed()?,
+                    material,
+                ))
+            }
+            "cone" => {
+                keys(&["min", "max", "closed"])?;
+                Box::new(Cone::truncated_with_mat(
+                    number("min", f64::NEG_INFINITY)?,
+                    number("max", f64::INFINITY)?,
+                    closed()?,
+                    material,
+                ))
+            }
+            "triangle" => {
+                keys(&["p1", "p2", "p3"])?;
+                let mut t = Triangle::new(point("p1")?, point("p2")?, point("p3")?);
+                t.set_material(material);
+                Box::new(t)
+            }
+            "group" => {
+                keys(&["children"])?;
+                let mut g = Group::new();
+                for child in require(node, entries, "children")?.as_list()? {
+                    g.add_child(self.shape(child, &material, depth + 1)?);
+                }
+                Box::new(g)
+            }
+            "csg" => {
+                keys(&["operation", "left", "right"])?;
+                let op = require(node, entries, "operation")?;
+                let operation = match op.as_str()? {
+                    "union" => CsgOp::Union,
+                    "intersection" => CsgOp::Intersection,
+                    "difference" => CsgOp::Difference,
+                    other => return error(op.line, format!("unknown csg operation '{other}'")),
+                };
+                let left = self.shape(require(node, entries, "left")?, &material, depth + 1)?;
+                let right = self.shape(require(node, entries, "right")?, &material, depth + 1)?;
+                Box::new(Csg::new(operation, left, right))
+            }
+            "obj" => {
+                keys(&["file"])?;
+                let file = require(node, entries, "file")?;
+                let name = file.as_str()?;
+                let input = std::fs::read_to_string(self.base_dir.join(name))
+                    .or_else(|e| error(file.line, format!("cannot read '{name}': {e}")))?;
+                let obj = parse_obj_file(&input)
+                    .or_else(|e| error(file.line, format!("in '{name}': {e}")))?;
+                let mut g = obj.to_group();
+                g.set_material(material);
+                Box::new(g)
+            }
+            other => return error(kind.line, format!("unknown object type '{other}'")),
+        };
+        if let Some(transform) = get(entries, "transform") {
+            let m = self.transform(transform, 0)?;
+            shape
+                .set_transform(m)
+                .or_else(|e| error(transform.line, e.to_string()))?;
+        }
+        Ok(shape)
+    }
+}
+
+pub fn parse_scene_file(input: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+    let document = parse_document(input)?;
+    let Value::List(items) = &document.value else {
+        return error(
+            document.line,
+            "scene file must be a list of 'add' and 'define' items".to_string(),
+        );
+    };
+    let mut builder = Builder {
+        base_dir,
+        defines: HashMap::new(),
+        camera: None,
+        world: World::new(),
+    };
+    for item in items {
+        let entries = item.as_map()?;
+        if get(entries, "define").is_some() {
+            builder.add_define(item, entries)?;
+            continue;
+        }
+        let kind = require(item, entries, "add")?;
+        match kind.as_str()? {
+            "camera" => builder.add_camera(item, entries)?,
+            "light" => builder.add_light(item, entries)?,
+            _ => {
+                let shape = builder.shape(item, &Material::default(), 0)?;
+                builder.world.objects.push(shape);
+            }
+        }
+    }
+    let Some(camera) = builder.camera else {
+        return error(
+            input.lines().count().max(1),
+            "scene has no camera".to_string(),
+        );
+    };
+    Ok(Scene {
+        camera,
+        world: builder.world,
+    })
+}
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::path::Path;
+
+    use crate::{
+        color::Color,
+        csg::{Csg, CsgOp},
+        cylinder::Cylinder,
+        group::Group,
+        matrix::Matrix,
+        object::Shape,
+        pattern::pattern_at,
+        tuple::Tuple,
+    };
+
+    use super::{parse_document, parse_scene_file, Node, Scene, SceneError, Value};
+
+    fn scalar(line: usize, s: &str) -> Node {
+        Node {
+            line,
+            value: Value::Scalar(s.to_string()),
+        }
+    }
+
+    fn parse(input: &str) -> Result<Scene, SceneError> {
+        parse_scene_file(&format!("{CAMERA}{input}"), Path::new("."))
+    }
+
+    fn parse_err(input: &str) -> SceneError {
+        match parse(input) {
+            Ok(_) => panic!("Should fail"),
+            Err(e) => e,
+        }
+    }
+
+    fn downcast<T: 'static>(s: &dyn Shape) -> &T {
+        (s as &dyn Any)
+            .downcast_ref::<T>()
+            .expect("Should have the expected type")
+    }
+
+    // Seven lines, so the first line of test input is line 8.
+    const CAMERA: &str = "- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [ 0, 1.5, -5 ]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+";
+
+    #[test]
+    fn test_parse_document_structure() {
+        let doc = parse_document(
+            "# leading comment
+- add: light # trailing comment
+  at: [ -10, 10, -10 ]
+  nested:
+    - a
+    - [b, [c, 'd # e']]
+- { x: 1, y: \"two\" }
+",
+        )
+        .expect("Should parse");
+        let Value::List(items) = doc.value else {
+            panic!("expected a list");
+        };
+        assert_eq!(items.len(), 2);
+        let Value::Map(first) = &items[0].value else {
+            panic!("expected a map");
+        };
+        assert_eq!(first[0], ("add".to_string(), scalar(2, "light")));
+        assert_eq!(
+            first[1].1.value,
+            Value::List(vec![scalar(3, "-10"), scalar(3, "10"), scalar(3, "-10")])
+        );
+        let Value::List(nested) = &first[2].1.value else {
+            panic!("expected a list");
+        };
+        assert_eq!(nested[0], scalar(5, "a"));
+        assert_eq!(
+            nested[1].value,
+            Value::List(vec![
+                scalar(6, "b"),
+                Node {
+                    line: 6,
+                    value: Value::List(vec![scalar(6, "c"), scalar(6, "d # e")])
+                }
+            ])
+        );
+        assert_eq!(
+            items[1].value,
+            Value::Map(vec![
+                ("x".to_string(), scalar(7, "1")),
+                ("y".to_string(), scalar(7, "two"))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_camera_and_light() {
+        let scene = parse(
+            "- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 0.5, 1]
+",
+        )
+        .expect("Should parse");
+        assert_eq!(scene.camera.hsize, 100);
+        assert_eq!(scene.camera.vsize, 50);
+        assert_eq!(scene.camera.field_of_view, 0.785);
+        assert_eq!(
+            scene.camera.transform(),
+            &Matrix::view_transform(
+                Tuple::point(0., 1.5, -5.),
+                Tuple::point(0., 1., 0.),
+                Tuple::vector(0., 1., 0.)
+            )
+        );
+        assert_eq!(scene.world.lights.len(), 1);
+        assert_eq!(
+            scene.world.lights[0].position,
+            Tuple::point(-10., 10., -10.)
+        );
+        assert_eq!(scene.world.lights[0].intensity, Color::new(1., 0.5, 1.));
+    }
+
+    #[test]
+    fn test_parse_shapes_with_materials_and_transforms() {
+        let scene = parse(
+            "- add: sphere
+  material:
+    color: [1, 0, 0]
+    ambient: 0.2
+    reflective: 0.5
+    refractive-index: 1.5
+  transform:
+    - [scale, 2, 2, 2]
+    - [translate, 1, 0, 0]
+- add: cylinder
+  min: 0
+  max: 2
+  closed: true
+",
+        )
+        .expect("Should parse");
+        assert_eq!(scene.world.objects.len(), 2);
+        let sphere = &scene.world.objects[0];
+        let mat = sphere.material();
+        assert_eq!(mat.color, Color::new(1., 0., 0.));
+        assert_eq!(mat.ambiant, 0.2);
+        assert_eq!(mat.reflective, 0.5);
+        assert_eq!(mat.refractive_index, 1.5);
+        // Transforms apply in list order.
+        assert_eq!(
+            sphere.transform(),
+            &(Matrix::translation(1., 0., 0.) * Matrix::scale(2., 2., 2.))
+        );
+        let cyl = downcast::<Cylinder>(scene.world.objects[1].as_ref());
+        assert_eq!((cyl.minimum, cyl.maximum, cyl.closed), (0., 2., true));
+    }
+
+    #[test]
+    fn test_define_and_extend() {
+        let scene = parse(
+            "- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.5, 0.8, 0.9]
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+- define: large-object
+  value:
+    - standard-transform
+    - [scale, 4, 4, 4]
+- add: cube
+  material: blue-material
+  transform:
+    - large-object
+    - [translate, 0, 0, 7]
+",
+        )
+        .expect("Should parse");
+        let cube = &scene.world.objects[0];
+        assert_eq!(cube.material().color, Color::new(0.5, 0.8, 0.9));
+        assert_eq!(cube.material().diffuse, 0.7);
+        let expected = Matrix::translation(0., 0., 7.)
+            * Matrix::scale(4., 4., 4.)
+            * Matrix::scale(0.5, 0.5, 0.5)
+            * Matrix::translation(1., -1., 1.);
+        assert_eq!(cube.transform(), &expected);
+    }
+
+    #[test]
+    fn test_add_defined_object() {
+        let scene = parse(
+            "- define: red-ball
+  value:
+    add: sphere
+    material: { color: [1, 0, 0] }
+- add: red-ball
+  transform: [[translate, 0, 2, 0]]
+",
+        )
+        .expect("Should parse");
+        let ball = &scene.world.objects[0];
+        assert_eq!(ball.material().color, Color::new(1., 0., 0.));
+        assert_eq!(ball.transform(), &Matrix::translation(0., 2., 0.));
+    }
+
+    #[test]
+    fn test_groups_and_csg_inherit_material() {
+        let scene = parse(
+            "- add: group
+  material:
+    color: [0, 1, 0]
+  children:
+    - add: sphere
+    - add: csg
+      operation: difference
+      left:
+        add: cube
+      right:
+        add: sphere
+        material:
+          color: [0, 0, 1]
+",
+        )
+        .expect("Should parse");
+        let group = downcast::<Group>(scene.world.objects[0].as_ref());
+        let children = group.children();
+        assert_eq!(children[0].material().color, Color::new(0., 1., 0.));
+        let csg = downcast::<Csg>(children[1].as_ref());
+        assert_eq!(csg.operation, CsgOp::Difference);
+        assert_eq!(csg.left().material().color, Color::new(0., 1., 0.));
+        assert_eq!(csg.right().material().color, Color::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let scene = parse(
+            "- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [1, 1, 1]
+        - [0, 0, 0]
+      transform:
+        - [scale, 0.5, 0.5, 0.5]
+",
+        )
+        .expect("Should parse");
+        let mat = scene.world.objects[0].material();
+        let pattern = mat.pattern.as_ref().expect("Should have a pattern");
+        assert_eq!(
+            pattern_at(pattern.as_ref(), Tuple::point(0.25, 0., 0.)),
+            Color::new(1., 1., 1.)
+        );
+        assert_eq!(
+            pattern_at(pattern.as_ref(), Tuple::point(0.75, 0., 0.)),
+            Color::new(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn test_obj_file() {
+        let dir = std::env::temp_dir().join(format!("scene-file-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("Should create dir");
+        std::fs::write(dir.join("tri.obj"), "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n")
+            .expect("Should write obj");
+        let input = format!(
+            "{CAMERA}- add: obj
+  file: tri.obj
+  material:
+    color: [1, 0, 0]
+- add: obj
+  file: missing.obj
+"
+        );
+        let err = match parse_scene_file(&input, &dir) {
+            Ok(_) => panic!("Should fail"),
+            Err(e) => e,
+        };
+        assert_eq!(err.line, 13);
+        assert!(err.message.starts_with("cannot read 'missing.obj'"));
+
+        let input = input.lines().take(11).collect::<Vec<_>>().join("\n");
+        let scene = parse_scene_file(&input, &dir).expect("Should parse");
+        std::fs::remove_dir_all(&dir).expect("Should remove dir");
+        let group = downcast::<Group>(scene.world.objects[0].as_ref());
+        assert_eq!(group.children().len(), 1);
+        assert_eq!(group.children()[0].material().color, Color::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let cases = [
+            (
+                "- add: sphere\n  colour: [1, 0, 0]\n",
+                9,
+                "unknown key 'colour' for sphere",
+            ),
+            ("- add: teapot\n", 8, "unknown object type 'teapot'"),
+            (
+                "- add: light\n  at: [1, 2]\n  intensity: [1, 1, 1]\n",
+                9,
+                "expected 3 numbers, found 2",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n",
+                10,
+                "transform is not invertible",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [rotate-x, a]\n",
+                10,
+                "expected a number, found 'a'",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [spin, 1]\n",
+                10,
+                "unknown transform 'spin'",
+            ),
+            (
+                "- add: sphere\n  material: shiny\n",
+                9,
+                "unknown define 'shiny'",
+            ),
+            (
+                "- add: sphere\n   material: {}\n",
+                9,
+                "unexpected indentation",
+            ),
+            (
+                "- add: sphere\n  transform: [[scale, 1, 1, 1]\n",
+                9,
+                "expected ',' or ']'",
+            ),
+            (
+                "- add: sphere\n  material:\n",
+                9,
+                "missing value for 'material'",
+            ),
+            ("- add: sphere\n  add: cube\n", 9, "duplicate key 'add'"),
+            (
+                "- define: loop\n  value: [loop]\n- add: sphere\n  transform: loop\n",
+                9,
+                "defines nested too deeply",
+            ),
+            (
+                "- define: loop\n  value: {add: group, children: [{add: loop}]}\n- add: loop\n",
+               9,
+                "defines nested too deeply",
+            ),
+            (
+                "- define: loop\n  value: {add: csg, operation: union, left: {add: loop}, right: {add: sphere}}\n- add: loop\n",
+               9,
+                "defines nested too deeply",
+            ),
+            (
+                "- define: p\n  value: {type: stripes, patterns: [p, p]}\n- add: sphere\n  material: {pattern: p}\n",
+                9,
+                "defines nested too deeply",
+            ),
+        ];
+        for (input, line, message) in cases {
+            assert_eq!(
+                parse_err(input),
+                SceneError {
+                    line,
+                    message: message.to_string()
+                },
+                "{input}"
+            );
+        }
+
+        let err = match parse_scene_file(
+            "- add: light\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]\n",
+            Path::new("."),
+        ) {
+            Ok(_) => panic!("Should fail"),
+            Err(e) => e,
+        };
+        assert_eq!(err.to_string(), "line 3: scene has no camera");
+
+        let err = match parse_scene_file(
+            "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [0, 0, 0]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n",
+            Path::new("."),
+        ) {
+            Ok(_) => panic!("Should fail"),
+            Err(e) => e,
+        };
+        assert_eq!(
+            err.to_string(),
+            "line 1: camera view transform is not invertible"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::Intersection,
+    material::Material,
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sphere {
+    data: ShapeData,
+}
+impl Sphere {
+    pub fn new() -> Self {
+        Sphere {
+            data: ShapeData::new(),
+        }
+    }
+    pub fn with_mat(material: Material) -> Self {
+        Sphere {
+            data: ShapeData::with_mat(material),
+        }
+    }
+}
+impl Default for Sphere {
+    fn default() -> Self {
+        Sphere::new()
+    }
+}
+impl Shape for Sphere {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let sphere_to_ray = ray.origin - Tuple::point(0., 0., 0.);
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2. * ray.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0. {
+            Intersections::new()
+        } else {
+            Intersections::from([
+                Intersection::new((-b - f64::sqrt(discriminant)) / (2.0 * a), self),
+                Intersection::new((-b + f64::sqrt(discriminant)) / (2.0 * a), self),
+            ])
+        }
+    }
+
+    fn local_normal_at(&self, object_point: Tuple, _hit: &Intersection) -> Tuple {
+        object_point - Tuple::point(0., 0., 0.)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        intersection::Intersection,
+        material::Material,
+        matrix::Matrix,
+        object::{normal_at, Shape},
+        tuple::Tuple,
+    };
+
+    use super::Sphere;
+
+    #[test]
+    fn default_transformation() {
+        let s = Sphere::new();
+        assert_eq!(*s.transform(), Matrix::identity());
+        assert_eq!(*s.material(), Material::default());
+    }
+
+    #[test]
+    fn default_translation() {
+        let mut s = Sphere::new();
+        let m = Matrix::translation(2., 3., 4.);
+        s.set_transform(m.clone())
+            .expect("Transform should be invertible");
+        assert_eq!(*s.transform(), m);
+        assert_eq!(*s.material(), Material::default());
+    }
+
+    #[test]
+    fn test_normal_at_1() {
+        let s = Sphere::new();
+        let n = normal_at(&s, Tuple::point(1., 0., 0.), &Intersection::new(0., &s));
+        assert_eq!(n, Tuple::vector(1., 0., 0.));
+    }
+
+    #[test]
+    fn test_normal_at_2() {
+        let s = Sphere::new();
+        let n = normal_at(&s, Tuple::point(0., 1., 0.), &Intersection::new(0., &s));
+        assert_eq!(n, Tuple::vector(0., 1., 0.));
+    }
+
+    #[test]
+    fn test_normal_at_3() {
+        let s = Sphere::new();
+        let n = normal_at(&s, Tuple::point(0., 0., 1.), &Intersection::new(0., &s));
+        assert_eq!(n, Tuple::vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_normal_at_4() {
+        let s = Sphere::new();
+        let n = normal_at(
+            &s,
+            Tuple::point(f64::sqrt(3.) / 3., f64::sqrt(3.) / 3., f64::sqrt(3.) / 3.),
+            &Intersection::new(0., &s),
+        );
+        assert_eq!(
+            n,
+            Tuple::vector(f64::sqrt(3.) / 3., f64::sqrt(3.) / 3., f64::sqrt(3.) / 3.)
+        );
+    }
+
+    #[test]
+    fn test_normal_on_a_translate_sphere() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(0., 1., 0.))
+            .expect("Transform should be invertible");
+        let n = normal_at(
+            &s,
+            Tuple::point(0., 1.70711, -0.70711),
+            &Intersection::new(0., &s),
+        );
+        let expected = Tuple::vector(0., 0.70711, -0.70711);
+        assert!(n.x - expected.x < 0.001);
+        assert!(n.y - expected.y < 0.001);
+        assert!(n.z - expected.z < 0.001);
+        assert!(n.w - expected.w < 0.001);
+    }
+    #[test]
+    fn test_normal_on_a_scale_sphere() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::scale(1., 0.5, 1.) * Matrix::rot_z(PI / 5.))
+            .expect("Transform should be invertible");
+        let n = normal_at(
+            &s,
+            Tuple::point(0., f64::sqrt(2.) / 2., -f64::sqrt(2.) / 2.),
+            &Intersection::new(0., &s),
+        );
+        let expected = Tuple::vector(0., 0.97014, -0.24254);
+        assert!(n.x - expected.x < 0.001);
+        assert!(n.y - expected.y < 0.001);
+        assert!(n.z - expected.z < 0.001);
+        assert!(n.w - expected.w < 0.001);
+    }
+
+    #[test]
+    fn test_sphere_with_mat() {
+        let mut mat = Material::default();
+        mat.ambiant = 1.;
+        let s = Sphere::with_mat(mat.clone());
+
+        assert_eq!(*s.material(), mat)
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    bounds::BoundingBox,
+    intersect::Intersections,
+    intersection::{Intersection, EPSILON},
+    object::{Shape, ShapeData},
+    ray::Ray,
+    tuple::Tuple,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    data: ShapeData,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+}
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Triangle {
+            data: ShapeData::new(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    data: ShapeData,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+}
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> Self {
+        SmoothTriangle {
+            data: ShapeData::new(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+}
+
+// Möller–Trumbore, returning (t, u, v) when the ray crosses the triangle.
+fn triangle_intersect(ray: &Ray, p1: Tuple, e1: Tuple, e2: Tuple) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = ray.direction.cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let f = 1. / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction.dot(&origin_cross_e1);
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    Some((f * e2.dot(&origin_cross_e1), u, v))
+}
+
+fn triangle_bounds(p1: Tuple, p2: Tuple, p3: Tuple) -> BoundingBox {
+    let mut b = BoundingBox::empty();
+    b.add_point(p1);
+    b.add_point(p2);
+    b.add_point(p3);
+    b
+}
+
+impl Shape for Triangle {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        match triangle_intersect(ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => Intersections::from([Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::new(),
+        }
+    }
+
+    fn local_normal_at(&self, _object_point: Tuple, _hit: &Intersection) -> Tuple {
+        self.normal
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        triangle_bounds(self.p1, self.p2, self.p3)
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        match triangle_intersect(ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => Intersections::from([Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::new(),
+        }
+    }
+
+    fn local_normal_at(&self, _object_point: Tuple, hit: &Intersection) -> Tuple {
+        self.n2 * hit.u + self.n3 * hit.v + self.n1 * (1. - hit.u - hit.v)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        triangle_bounds(self.p1, self.p2, self.p3)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::{prepare_computations, Intersection},
+        object::{normal_at, Shape},
+        ray::Ray,
+        tuple::Tuple,
+    };
+
+    use super::{SmoothTriangle, Triangle};
+
+    fn default_triangle() -> Triangle {
+        Triangle::new(
+            Tuple::point(0., 1., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+        )
+    }
+
+    fn default_smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Tuple::point(0., 1., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+            Tuple::vector(-1., 0., 0.),
+            Tuple::vector(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn test_construct_triangle() {
+        let t = default_triangle();
+        assert_eq!(t.p1, Tuple::point(0., 1., 0.));
+        assert_eq!(t.p2, Tuple::point(-1., 0., 0.));
+        assert_eq!(t.p3, Tuple::point(1., 0., 0.));
+        assert_eq!(t.e1, Tuple::vector(-1., -1., 0.));
+        assert_eq!(t.e2, Tuple::vector(1., -1., 0.));
+        assert_eq!(t.normal, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_normal_on_triangle() {
+        let t = default_triangle();
+        let i = Intersection::new(0., &t);
+        assert_eq!(t.local_normal_at(Tuple::point(0., 0.5, 0.), &i), t.normal);
+        assert_eq!(
+            t.local_normal_at(Tuple::point(-0.5, 0.75, 0.), &i),
+            t.normal
+        );
+        assert_eq!(t.local_normal_at(Tuple::point(0.5, 0.25, 0.), &i), t.normal);
+    }
+
+    #[test]
+    fn test_ray_parallel_to_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 0.));
+        assert_eq!(t.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_ray_misses_triangle_edges() {
+        let t = default_triangle();
+        let cases = [
+            Tuple::point(1., 1., -2.),
+            Tuple::point(-1., 1., -2.),
+            Tuple::point(0., -1., -2.),
+        ];
+        for origin in cases {
+            let r = Ray::new(origin, Tuple::vector(0., 0., 1.));
+            assert_eq!(t.local_intersect(&r).len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_ray_strikes_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Tuple::point(0., 0.5, -2.), Tuple::vector(0., 0., 1.));
+        let mut xs = t.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 2.);
+    }
+
+    #[test]
+    fn test_construct_smooth_triangle() {
+        let t = default_smooth_triangle();
+        assert_eq!(t.p1, Tuple::point(0., 1., 0.));
+        assert_eq!(t.n1, Tuple::vector(0., 1., 0.));
+        assert_eq!(t.n3, Tuple::vector(1., 0., 0.));
+    }
+
+    #[test]
+    fn test_smooth_triangle_stores_uv() {
+        let t = default_smooth_triangle();
+        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.), Tuple::vector(0., 0., 1.));
+        let mut xs = t.local_intersect(&r);
+        let i = xs.pop().expect("Should have intersection");
+        assert!((i.u - 0.45).abs() < 0.0001);
+        assert!((i.v - 0.25).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_smooth_triangle_interpolates_normal() {
+        let t = default_smooth_triangle();
+        let i = Intersection::with_uv(1., &t, 0.45, 0.25);
+        let n = normal_at(&t, Tuple::point(0., 0., 0.), &i);
+        let expected = Tuple::vector(-0.5547, 0.83205, 0.);
+        assert!((n.x - expected.x).abs() < 0.0001);
+        assert!((n.y - expected.y).abs() < 0.0001);
+        assert!((n.z - expected.z).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_prepare_computations_smooth_triangle() {
+        let t = default_smooth_triangle();
+        let i = Intersection::with_uv(1., &t, 0.45, 0.25);
+        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.), Tuple::vector(0., 0., 1.));
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+        let expected = Tuple::vector(-0.5547, 0.83205, 0.);
+        assert!((comps.normalv.x - expected.x).abs() < 0.0001);
+        assert!((comps.normalv.y - expected.y).abs() < 0.0001);
+        assert!((comps.normalv.z - expected.z).abs() < 0.0001);
+    }
+}
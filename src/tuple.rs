@@ -143,6 +143,14 @@ impl ops::Neg for Tuple {
     }
 }
 
+#[cfg(test)]
+pub fn assert_tuple_eq(t: Tuple, expected: Tuple) {
+    assert!((t.x - expected.x).abs() < 0.0001);
+    assert!((t.y - expected.y).abs() < 0.0001);
+    assert!((t.z - expected.z).abs() < 0.0001);
+    assert!((t.w - expected.w).abs() < 0.0001);
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tuple;
@@ -0,0 +1,535 @@
+use crate::color::Color;
+use crate::intersect::{hit, intersect, sorted, Intersections};
+use crate::intersection::{prepare_computations, schlick, Computations};
+use crate::material::{lighting, Material};
+use crate::matrix::Matrix;
+use crate::object::Shape;
+use crate::point_light::Point_Light;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+use crate::tuple::Tuple;
+
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<Point_Light>,
+    pub max_depth: usize,
+}
+impl World {
+    pub fn new() -> Self {
+        World {
+            objects: vec![],
+            lights: vec![],
+            max_depth: 5,
+        }
+    }
+    pub fn default_world() -> Self {
+        let light = Point_Light::new(Color::new(1., 1., 1.), Tuple::point(-10., 10., -10.));
+        let mut mat = Material::default();
+        mat.color = Color::new(0.8, 1.0, 0.6);
+        mat.diffuse = 0.7;
+        mat.specular = 0.2;
+        let s1 = Sphere::with_mat(mat);
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::scale(0.5, 0.5, 0.5))
+            .expect("Transform should be invertible");
+        World {
+            objects: vec![Box::new(s1), Box::new(s2)],
+            lights: vec![light],
+            max_depth: 5,
+        }
+    }
+
+    pub fn intersect(&self, r: &Ray) -> Intersections<'_> {
+        self.objects
+            .iter()
+            .flat_map(|o| intersect(r, o.as_ref()))
+            .collect()
+    }
+
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        let material = comps.o.material();
+        let surface = self
+            .lights
+            .iter()
+            .map(|light| {
+                lighting(
+                    material,
+                    light,
+                    &comps.over_point,
+                    &comps.object_point,
+                    &comps.eyev,
+                    &comps.normalv,
+                    self.is_shadowed(&comps.over_point, light),
+                )
+            })
+            .fold(Color::new(0., 0., 0.), |acc, c| acc + c);
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+        if material.reflective > 0. && material.transparency > 0. {
+            let reflectance = schlick(comps);
+            surface + reflected * reflectance + refracted * (1. - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.o.material().reflective;
+        if remaining == 0 || reflective == 0. {
+            return Color::new(0., 0., 0.);
+        }
+        let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+        self.color_at_depth(&reflect_ray, remaining - 1) * reflective
+    }
+
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let transparency = comps.o.material().transparency;
+        if remaining == 0 || transparency == 0. {
+            return Color::new(0., 0., 0.);
+        }
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio * n_ratio * (1. - cos_i * cos_i);
+        if sin2_t > 1. {
+            return Color::new(0., 0., 0.);
+        }
+        let cos_t = (1. - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let refract_ray = Ray::new(comps.under_point, direction);
+        self.color_at_depth(&refract_ray, remaining - 1) * transparency
+    }
+
+    pub fn is_shadowed(&self, point: &Tuple, light: &Point_Light) -> bool {
+        let v = light.position - *point;
+        let distance = v.magnitude();
+        let r = Ray::new(*point, v.normalize());
+        let xs = self.intersect(&r);
+        match hit(&xs) {
+            Some(i) => i.t < distance,
+            None => false,
+        }
+    }
+
+    pub fn color_at(&self, r: &Ray) -> Color {
+        self.color_at_depth(r, self.max_depth)
+    }
+
+    pub fn color_at_depth(&self, r: &Ray, remaining: usize) -> Color {
+        let xs = sorted(self.intersect(r));
+        match xs.iter().find(|i| i.t > 0.) {
+            Some(i) => self.shade_hit(&prepare_computations(i, r, &xs), remaining),
+            None => Color::new(0., 0., 0.),
+        }
+    }
+}
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+    use std::sync::Arc;
+
+    use crate::{
+        color::Color,
+        intersection::{prepare_computations, Intersection},
+        material::Material,
+        matrix::{Matrix, Transform},
+        object::Shape,
+        pattern::{Pattern, Stripe},
+        plane::Plane,
+        point_light::Point_Light,
+        ray::Ray,
+        sphere::Sphere,
+        tuple::Tuple,
+    };
+
+    use super::World;
+
+    fn assert_color_eq(c: Color, expected: Color) {
+        assert!((c.r - expected.r).abs() < 0.0001);
+        assert!((c.g - expected.g).abs() < 0.0001);
+        assert!((c.b - expected.b).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_empty_world() {
+        let w = World::new();
+        assert_eq!(w.objects.len(), 0);
+        assert_eq!(w.lights.len(), 0);
+    }
+
+    #[test]
+    fn test_default_world() {
+        let w = World::default_world();
+        let mut mat = Material::default();
+        mat.color = Color::new(0.8, 1.0, 0.6);
+        mat.diffuse = 0.7;
+        mat.specular = 0.2;
+
+        assert_eq!(w.lights.len(), 1);
+        assert_eq!(w.lights[0].intensity, Color::new(1., 1., 1.));
+        assert_eq!(w.lights[0].position, Tuple::point(-10., 10., -10.));
+        assert_eq!(w.objects.len(), 2);
+        assert_eq!(*w.objects[0].transform(), Matrix::identity());
+        assert_eq!(*w.objects[0].material(), mat);
+        assert_eq!(*w.objects[1].transform(), Matrix::scale(0.5, 0.5, 0.5));
+        assert_eq!(*w.objects[1].material(), Material::default());
+    }
+
+    #[test]
+    fn test_intersect_world() {
+        let w = World::default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut xs = w.intersect(&r);
+
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 4.);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 4.5);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 5.5);
+        assert_eq!(xs.pop().expect("Should have intersection").t, 6.);
+    }
+
+    #[test]
+    fn test_shade_hit() {
+        let w = World::default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(4., w.objects[0].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_color_eq(
+            w.shade_hit(&comps, w.max_depth),
+            Color::new(0.38066, 0.47583, 0.2855),
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_inside() {
+        let mut w = World::default_world();
+        w.lights = vec![Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(0., 0.25, 0.),
+        )];
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(0.5, w.objects[1].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_color_eq(
+            w.shade_hit(&comps, w.max_depth),
+            Color::new(0.90498, 0.90498, 0.90498),
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_multiple_lights() {
+        let mut w = World::default_world();
+        w.lights.push(Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(-10., 10., -10.),
+        ));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(4., w.objects[0].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_color_eq(
+            w.shade_hit(&comps, w.max_depth),
+            Color::new(0.38066, 0.47583, 0.2855) * 2.,
+        );
+    }
+
+    #[test]
+    fn test_color_at_miss() {
+        let w = World::default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
+
+        assert_eq!(w.color_at(&r), Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_color_at_hit() {
+        let w = World::default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_color_eq(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn test_color_at_behind_ray() {
+        let mut w = World::default_world();
+        for o in w.objects.iter_mut() {
+            let mut mat = o.material().clone();
+            mat.ambiant = 1.;
+            o.set_material(mat);
+        }
+        let r = Ray::new(Tuple::point(0., 0., 0.75), Tuple::vector(0., 0., -1.));
+
+        assert_eq!(w.color_at(&r), Color::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn test_no_shadow_nothing_collinear() {
+        let w = World::default_world();
+        let p = Tuple::point(0., 10., 0.);
+        assert!(!w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn test_shadow_object_between_point_and_light() {
+        let w = World::default_world();
+        let p = Tuple::point(10., -10., 10.);
+        assert!(w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn test_no_shadow_object_behind_light() {
+        let w = World::default_world();
+        let p = Tuple::point(-20., 20., -20.);
+        assert!(!w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn test_no_shadow_object_behind_point() {
+        let w = World::default_world();
+        let p = Tuple::point(-2., 2., -2.);
+        assert!(!w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn test_shade_hit_in_shadow() {
+        let mut w = World::new();
+        w.lights.push(Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(0., 0., -10.),
+        ));
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0., 0., 10.))
+            .expect("Transform should be invertible");
+        w.objects.push(Box::new(Sphere::new()));
+        w.objects.push(Box::new(s2));
+        let r = Ray::new(Tuple::point(0., 0., 5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(4., w.objects[1].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+
+        assert_color_eq(w.shade_hit(&comps, w.max_depth), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn test_shade_hit_pattern_in_object_space() {
+        let mut w = World::new();
+        w.lights.push(Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(0., 0., -10.),
+        ));
+        let mut stripe = Stripe::new(Color::new(1., 1., 1.), Color::new(0., 0., 0.));
+        stripe
+            .set_transform(Matrix::translation(0.5, 0., 0.))
+            .expect("Transform should be invertible");
+        let mut mat = Material::default();
+        mat.pattern = Some(Arc::new(stripe));
+        mat.ambiant = 1.;
+        mat.diffuse = 0.;
+        mat.specular = 0.;
+        let mut s = Sphere::with_mat(mat);
+        s.set_transform(Matrix::scale(2., 2., 2.))
+            .expect("Transform should be invertible");
+        w.objects.push(Box::new(s));
+
+        let r = Ray::new(Tuple::point(1.5, 0., -5.), Tuple::vector(0., 0., 1.));
+        assert_color_eq(w.color_at(&r), Color::new(1., 1., 1.));
+        let r = Ray::new(Tuple::point(0.5, 0., -5.), Tuple::vector(0., 0., 1.));
+        assert_color_eq(w.color_at(&r), Color::new(0., 0., 0.));
+    }
+
+    fn reflective_floor() -> Plane {
+        let mut mat = Material::default();
+        mat.reflective = 0.5;
+        let mut p = Plane::with_mat(mat);
+        p.set_transform(Matrix::translation(0., -1., 0.))
+            .expect("Transform should be invertible");
+        p
+    }
+
+    #[test]
+    fn test_reflected_color_nonreflective() {
+        let mut w = World::default_world();
+        let mut mat = w.objects[1].material().clone();
+        mat.ambiant = 1.;
+        w.objects[1].set_material(mat);
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(1., w.objects[1].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+        assert_eq!(
+            w.reflected_color(&comps, w.max_depth),
+            Color::new(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn test_reflected_color_reflective() {
+        let mut w = World::default_world();
+        w.objects.push(Box::new(reflective_floor()));
+        let r = Ray::new(
+            Tuple::point(0., 0., -3.),
+            Tuple::vector(0., -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = Intersection::new(SQRT_2, w.objects[2].as_ref());
+        let comps = prepare_computations(&i, &r, std::slice::from_ref(&i));
+        assert_color_eq(
+            w.reflected_color(&comps, w.max_depth),
+            Color::new(0.19033, 0.23791, 0.14274),
+        );
+        assert_color_eq(
+            w.shade_hit(&comps, w.max_depth),
+            Color::new(0.87676, 0.92434, 0.82917),
+        );
+        assert_eq!(w.reflected_color(&comps, 0), Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_mutually_reflective_surfaces_terminate() {
+        let mut w = World::new();
+        w.lights.push(Point_Light::new(
+            Color::new(1., 1., 1.),
+            Tuple::point(0., 0., 0.),
+        ));
+        let mut mat = Material::default();
+        mat.reflective = 1.;
+        let mut lower = Plane::with_mat(mat.clone());
+        lower
+            .set_transform(Matrix::translation(0., -1., 0.))
+            .expect("Transform should be invertible");
+        let mut upper = Plane::with_mat(mat);
+        upper
+            .set_transform(Matrix::rot_x(PI) * Matrix::translation(0., -1., 0.))
+            .expect("Transform should be invertible");
+        w.objects.push(Box::new(lower));
+        w.objects.push(Box::new(upper));
+
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        let c = w.color_at(&r);
+        assert!(c.r > 0. && c.r.is_finite());
+    }
+
+    #[derive(Debug)]
+    struct PointPattern {
+        transform: Transform,
+    }
+    impl Pattern for PointPattern {
+        fn transform_data(&self) -> &Transform {
+            &self.transform
+        }
+        fn transform_data_mut(&mut self) -> &mut Transform {
+            &mut self.transform
+        }
+        fn local_pattern_at(&self, p: Tuple) -> Color {
+            Color::new(p.x, p.y, p.z)
+        }
+    }
+
+    fn make_transparent(o: &mut dyn Shape, refractive_index: f64) {
+        let mut mat = o.material().clone();
+        mat.transparency = 1.;
+        mat.refractive_index = refractive_index;
+        o.set_material(mat);
+    }
+
+    #[test]
+    fn test_refracted_color_opaque_and_max_depth() {
+        let mut w = World::default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = vec![
+            Intersection::new(4., w.objects[0].as_ref()),
+            Intersection::new(6., w.objects[0].as_ref()),
+        ];
+        let comps = prepare_computations(&xs[0], &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0., 0., 0.));
+
+        make_transparent(w.objects[0].as_mut(), 1.5);
+        let xs = vec![
+            Intersection::new(4., w.objects[0].as_ref()),
+            Intersection::new(6., w.objects[0].as_ref()),
+        ];
+        let comps = prepare_computations(&xs[0], &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_refracted_color_total_internal_reflection() {
+        let mut w = World::default_world();
+        make_transparent(w.objects[0].as_mut(), 1.5);
+        let r = Ray::new(
+            Tuple::point(0., 0., FRAC_1_SQRT_2),
+            Tuple::vector(0., 1., 0.),
+        );
+        let xs = vec![
+            Intersection::new(-FRAC_1_SQRT_2, w.objects[0].as_ref()),
+            Intersection::new(FRAC_1_SQRT_2, w.objects[0].as_ref()),
+        ];
+        let comps = prepare_computations(&xs[1], &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_refracted_color_with_refracted_ray() {
+        let mut w = World::default_world();
+        let mut mat = w.objects[0].material().clone();
+        mat.ambiant = 1.;
+        mat.pattern = Some(Arc::new(PointPattern {
+            transform: Transform::default(),
+        }));
+        w.objects[0].set_material(mat);
+        make_transparent(w.objects[1].as_mut(), 1.5);
+        let r = Ray::new(Tuple::point(0., 0., 0.1), Tuple::vector(0., 1., 0.));
+        let xs = vec![
+            Intersection::new(-0.9899, w.objects[0].as_ref()),
+            Intersection::new(-0.4899, w.objects[1].as_ref()),
+            Intersection::new(0.4899, w.objects[1].as_ref()),
+            Intersection::new(0.9899, w.objects[0].as_ref()),
+        ];
+        let comps = prepare_computations(&xs[2], &r, &xs);
+        assert_color_eq(
+            w.refracted_color(&comps, 5),
+            Color::new(0., 0.99888, 0.04725),
+        );
+    }
+
+    fn transparent_floor(reflective: f64) -> Plane {
+        let mut mat = Material::default();
+        mat.transparency = 0.5;
+        mat.refractive_index = 1.5;
+        mat.reflective = reflective;
+        let mut floor = Plane::with_mat(mat);
+        floor
+            .set_transform(Matrix::translation(0., -1., 0.))
+            .expect("Transform should be invertible");
+        floor
+    }
+
+    #[test]
+    fn test_shade_hit_transparent_material() {
+        for (reflective, expected) in [
+            (0., Color::new(0.93642, 0.68642, 0.68642)),
+            (0.5, Color::new(0.93391, 0.69643, 0.69243)),
+        ] {
+            let mut w = World::default_world();
+            w.objects.push(Box::new(transparent_floor(reflective)));
+            let mut mat = Material::default();
+            mat.color = Color::new(1., 0., 0.);
+            mat.ambiant = 0.5;
+            let mut ball = Sphere::with_mat(mat);
+            ball.set_transform(Matrix::translation(0., -3.5, -0.5))
+                .expect("Transform should be invertible");
+            w.objects.push(Box::new(ball));
+
+            let r = Ray::new(
+                Tuple::point(0., 0., -3.),
+                Tuple::vector(0., -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+            );
+            let xs = vec![Intersection::new(SQRT_2, w.objects[2].as_ref())];
+            let comps = prepare_computations(&xs[0], &r, &xs);
+            assert_color_eq(w.shade_hit(&comps, 5), expected);
+        }
+    }
+}